@@ -1,7 +1,7 @@
 'use strict'
 
 const pages = {};
-const data = { messages: [], startGame: [], endGame: [], gameInput: [], rooms: [] }
+const data = { messages: [], startGame: [], endGame: [], gameInput: [], gameProgress: [], sequences: [], boards: [], rooms: [], roster: [] }
 
 async function loadPage(page) {
   const obj = document.body;
@@ -60,9 +60,25 @@ function extractValue(selector) {
   return value;
 }
 
+// Tags are PascalCase in the unversioned protocol and snake_case in the envelope's type
+const snakeCase = (tag) => tag.replace(/[A-Z]/g, (c, i) => (i > 0 ? "_" : "") + c.toLowerCase());
+const pascalCase = (type) => type.split("_").map((word) => word.charAt(0).toUpperCase() + word.substring(1)).join("");
+
+// { Join: "room" } becomes { v: 1, type: "join", data: "room" }
+function wrap(obj) {
+  const tag = Object.keys(obj)[0];
+  return { v: version, type: snakeCase(tag), data: obj[tag] };
+}
+
+// Envelopes are unwrapped to the unversioned shape, so the router handles both
+function unwrap(obj) {
+  if (obj === null || typeof obj !== "object" || obj.v == null) return obj;
+  return { [pascalCase(obj.type)]: obj.data === undefined ? null : obj.data };
+}
+
 function send(obj) {
   console.log(obj);
-  const json = JSON.stringify(obj);
+  const json = JSON.stringify(version > 0 ? wrap(obj) : obj);
   try {
     conn.send(json);
   } catch {}
@@ -74,7 +90,7 @@ function titleCase(text) {
 
 function parseJson(msg) {
   try {
-    return JSON.parse(msg);
+    return unwrap(JSON.parse(msg));
   } catch (e) {
     alert("Unable to parse received message: (" + e + ") " + msg);
   }
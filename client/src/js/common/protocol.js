@@ -1,5 +1,8 @@
 'use strict'
 
+// Asks the server to speak a protocol version, it replies with `Welcome`
+const sendHello = (requested) => { try { conn.send(JSON.stringify({ v: requested, type: "hello", data: { version: requested } })) } catch {} };
+
 const sendListRooms = () => { try { conn.send('"ListRooms"') } catch {} };
 
 const sendName = (name) => send({ Name: name });
@@ -8,6 +11,33 @@ const sendJoinRoom = (room) => send({ Join: room });
 
 const sendMessage = (msg) => send({ Message: msg });
 
+const sendTeamMode = (enabled) => send({ TeamMode: enabled });
+
+const sendTeam = (team) => send({ Team: team });
+
+const sendTeamMessage = (msg) => send({ TeamMessage: msg });
+
+// Only the room's host may change it, e.g. { weights: { MathSprint: 2 }, no_repeat: true, order: [] }
+const sendPlaylist = (playlist) => send({ Playlist: playlist });
+
+// Only the room's host may pause or resume it
+const sendPause = () => { try { conn.send('"Pause"') } catch {} };
+
+const sendResume = () => { try { conn.send('"Resume"') } catch {} };
+
+// Durations in milliseconds, e.g. { countdown: 3000, results: 15000, match_over: 10000, games: 10 }
+const sendSettings = (settings) => send({ Settings: settings });
+
+const sendSkip = () => { try { conn.send('"Skip"') } catch {} };
+
+// Index of one of the candidates received in `NextCandidates`
+const sendVote = (candidate) => send({ Vote: candidate });
+
+// Only the room's host may add bots, e.g. { rock_papiuro_scissor: "Adaptive", recall: 0.75 }
+const sendAddBot = (settings) => send({ AddBot: settings });
+
+const sendRemoveBot = (name) => send({ RemoveBot: name });
+
 const sendRockPapiuroScissorInput = (button) => send({
   Game: { RockPapiuroScissor: button }
 });
@@ -15,3 +45,45 @@ const sendRockPapiuroScissorInput = (button) => send({
 const sendTheRightIuroInput = (vector) => send({
   Game: { TheRightIuro: vector }
 });
+
+const sendMathSprintInput = (answer) => send({
+  Game: { MathSprint: answer }
+});
+
+const sendIuroSaysInput = (sequence) => send({
+  Game: { IuroSays: sequence }
+});
+
+const sendWordScrambleInput = (word) => send({
+  Game: { WordScramble: word }
+});
+
+const sendMajorityInput = (option) => send({
+  Game: { Majority: option }
+});
+
+const sendPrisonersDilemmaInput = (choice) => send({
+  Game: { PrisonersDilemma: choice }
+});
+
+// Only what was typed since last update must be sent, at most every 100ms
+const sendTypingRaceInput = (typed) => send({
+  Game: { TypingRace: typed }
+});
+
+const sendClosestGuessInput = (guess) => send({
+  Game: { ClosestGuess: guess }
+});
+
+const sendTicTacToeInput = (cell) => send({
+  Game: { TicTacToe: cell }
+});
+
+const sendConnectFourInput = (column) => send({
+  Game: { ConnectFour: column }
+});
+
+// Each tap may be sent as it happens, the server batches them
+const sendButtonMashInput = (taps) => send({
+  Game: { ButtonMash: taps }
+});
@@ -5,14 +5,42 @@
 async function route(obj) {
   console.log(obj);
 
-  if (obj.Rooms != null) {
+  if (obj.Welcome != null) {
+    version = obj.Welcome;
+  } else if (obj.Rooms != null) {
     data.rooms = obj.Rooms;
   } else if (obj.GameStarted != null) {
     // The timeout allows games to end before starting another,
     // so we don't race and close the game that just started
     setTimeout(() => data.startGame.push(obj.GameStarted), 100);
+  } else if (obj.GameProgress != null) {
+    data.gameProgress.push(obj.GameProgress);
+  } else if (obj.SequenceRevealed != null) {
+    data.sequences.push(obj.SequenceRevealed);
+  } else if (obj.BoardUpdated != null) {
+    data.boards.push(obj.BoardUpdated);
+  } else if (obj.NextUp != null) {
+    data.messages.push("Next up: " + obj.NextUp.join(", "));
+  } else if (obj.Teams != null) {
+    obj.Teams.forEach(({ team, members, wins }) =>
+      data.messages.push("Team " + (team + 1) + " (" + wins + " wins): " + members.join(", ")));
+  } else if (obj.Roster != null) {
+    // Round-trip times change every few seconds, so the roster isn't sent to chat
+    data.roster = obj.Roster;
+  } else if (obj.PhaseChanged != null) {
+    const [phase, millis] = obj.PhaseChanged;
+    data.messages.push(phase + (millis !== null ? " (" + Math.ceil(millis / 1000) + "s)" : ""));
+  } else if (obj.SkipVotes != null) {
+    data.messages.push("Votes to skip: " + obj.SkipVotes[0] + " of " + obj.SkipVotes[1]);
+  } else if (obj.NextCandidates != null) {
+    data.messages.push("Vote for next game: " + obj.NextCandidates.map((game, i) => i + ") " + game).join(", "));
+  } else if (obj.NextVotes != null) {
+    data.messages.push("Votes: " + obj.NextVotes.join(", "));
+  } else if (obj.GameRevealed != null) {
+    showReveal(obj.GameRevealed);
   } else if (obj.GameEnded != null) {
     data.messages.push("Game " + obj.GameEnded[0] + " ended");
+    if (obj.GameEnded[2] != null) showResults(obj.GameEnded[2]);
     data.endGame.push(obj.GameEnded);
   } else if (obj.Text != null) {
     data.messages.push(obj.Text);
@@ -22,3 +50,36 @@ async function route(obj) {
     alert("Unknown message:" + e.data);
   }
 }
+
+// Game specific results are sent to chat
+function showResults(results) {
+  if (results.Majority != null) {
+    const { minority_wins, votes } = results.Majority;
+    data.messages.push((minority_wins ? "Minority" : "Majority") + " scored, votes: " + votes.join(" x "));
+  } else if (results.TypingRace != null) {
+    results.TypingRace.forEach(({ name, position, accuracy }, index) =>
+      data.messages.push((index + 1) + ". " + name + " typed " + position + " characters, " + accuracy + "% accuracy"));
+  } else if (results.ClosestGuess != null) {
+    const { answer, guesses } = results.ClosestGuess;
+    data.messages.push("The answer was " + answer);
+    guesses.forEach(({ name, guess, distance }) =>
+      data.messages.push(name + " guessed " + guess + " (off by " + distance + ")"));
+  } else if (results.ButtonMash != null) {
+    const { taps, flagged } = results.ButtonMash;
+    Object.keys(taps).forEach((name) => data.messages.push(name + " tapped " + taps[name] + " times"));
+    if (flagged.length > 0) data.messages.push("Tapped too fast: " + flagged.join(", "));
+  }
+}
+
+// Answers are tagged with their kind, e.g. { Throw: "Rock" } or { Number: 42 }
+const formatAnswer = (answer) => {
+  const value = Object.values(answer)[0];
+  return Array.isArray(value) ? value.join(" ") : String(value);
+};
+
+// Every user's input is sent to chat when a game ends
+function showReveal({ game, answer, players }) {
+  if (answer !== null) data.messages.push(game + " answer: " + formatAnswer(answer));
+  players.forEach(({ name, input, points, winner }) =>
+    data.messages.push(name + (input !== null ? " sent " + formatAnswer(input) : "") + ", " + points + " points" + (winner ? " (winner)" : "")));
+}
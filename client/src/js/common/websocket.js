@@ -2,6 +2,7 @@
 
 const protocol = (window.location.protocol === 'https:' && 'wss://' || 'ws://');
 const uri = protocol + window.location.host + '/ws/';
+const sseUri = window.location.origin + '/sse/';
 
 const ping = new Uint8Array(1);
 ping[0] = 0x9;
@@ -10,6 +11,41 @@ ping[0] = 0x9;
 let conn = null;
 let name = null;
 let room = null;
+// Protocol version the server agreed to speak, until then the unversioned one is used
+let version = 0;
+// Proxies may break websockets, transports are alternated until one of them opens
+let useSse = false;
+
+// Mimics `WebSocket`, receiving with Server-Sent Events and sending with POST requests
+class SseConnection {
+  constructor(uri) {
+    this.uri = uri;
+    this.id = null;
+    this.source = new EventSource(uri);
+    // First event carries the session id, commands must be posted to it
+    this.source.addEventListener("session", async (e) => {
+      this.id = e.data;
+      await this.onopen();
+    });
+    this.source.onmessage = async (e) => await this.onmessage(e);
+    // Server forgets the session once the stream drops, so a new one must be started
+    this.source.onerror = () => {
+      this.source.close();
+      this.onclose();
+    };
+  }
+
+  send(data) {
+    if (this.id === null) throw new Error("SSE session not started");
+
+    // Pings are sent as a single binary byte by websockets
+    if (data instanceof Uint8Array) {
+      fetch(this.uri + this.id + "/heartbeat", { method: "POST" }).catch(() => {});
+    } else {
+      fetch(this.uri + this.id, { method: "POST", body: data }).catch(() => {});
+    }
+  }
+}
 
 document.addEventListener("DOMContentLoaded", async () => {
   // Ping
@@ -22,10 +58,19 @@ document.addEventListener("DOMContentLoaded", async () => {
 });
 
 async function connect() {
-  conn = new WebSocket(uri);
-  conn.binaryType = "arraybuffer";
+  let opened = false;
+  if (useSse) {
+    conn = new SseConnection(sseUri);
+  } else {
+    conn = new WebSocket(uri);
+    conn.binaryType = "arraybuffer";
+  }
   conn.onmessage = async (e) => await route(parseJson(e.data));
   conn.onopen = async () => {
+    opened = true;
+    version = 0;
+    sendHello(1);
+
     if (name !== null) {
       sendName(name)
     } else {
@@ -40,5 +85,8 @@ async function connect() {
       await loadPage("join");
     }
   }
-  conn.onclose = () => conn = null;
+  conn.onclose = () => {
+    if (!opened) useSse = !useSse;
+    conn = null;
+  };
 }
@@ -8,6 +8,9 @@ function registerRoom() {
   monitorQueue("room", "startGame", startGame);
   monitorQueue("room", "endGame", stopGame);
   monitorQueue("room", "gameInput", gameInput);
+  monitorQueue("room", "gameProgress", showProgress);
+  monitorQueue("room", "sequences", showSequence);
+  monitorQueue("room", "boards", showBoard);
 }
 
 function createMessage(ev) {
@@ -33,6 +36,20 @@ function gameInput({ game, input }) {
   }
 }
 
+function showProgress({ game, progress, locked_in }) {
+  const text = Object.keys(progress).map((player) => player + ": " + progress[player]).join(", ");
+  if (text.length > 0) showMessage(game + " - " + text);
+  if (locked_in.length > 0) showMessage(game + " - locked in: " + locked_in.join(", "));
+}
+
+function showSequence([sequence, players]) {
+  showMessage("Repeat " + sequence.length + " iuros, still playing: " + players.join(", "));
+}
+
+function showBoard({ board, next }) {
+  if (next !== null) showMessage("Board " + (board + 1) + ": " + next + "'s turn");
+}
+
 function updateLeaderboard() {
   var score_children = document.querySelector("#chat-leaderboard").children;
   var i = 0;
@@ -0,0 +1,238 @@
+//! Async client of the Iuro protocol, for bots, tests and tools
+//!
+//! Speaks JSON over the server's websocket using the server's own `Command` and `Response`
+//! types. The connection is kept alive with heartbeats, and if it drops the client reconnects,
+//! sending its name and room again so the user resumes where it was.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), iuro_client::ClientError> {
+//! use iuro_client::{Client, Event};
+//!
+//! let mut client = Client::connect("ws://localhost:8080/ws/").await?;
+//! client.name("bot").await?;
+//! client.join("lobby").await?;
+//! while let Some(event) = client.next_event().await {
+//!     if let Event::Response(response) = event {
+//!         println!("{:?}", response);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub use iuro_server::types;
+
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, warn};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::{net::TcpStream, sync::mpsc, task::JoinHandle, time};
+use tokio_tungstenite::{
+    connect_async, tungstenite, tungstenite::Message, MaybeTlsStream, WebSocketStream,
+};
+use types::{Command, GameInput, Response};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How often heartbeats are sent, same as the browser client
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// How long to wait before reconnecting
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+/// How many commands may wait to be sent
+const QUEUE_SIZE: usize = 64;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Unable to connect: {0}")]
+    Websocket(#[from] tungstenite::Error),
+    #[error("Connection was closed")]
+    Closed,
+}
+
+/// What happened to the connection
+#[derive(Debug)]
+pub enum Event {
+    /// Connection was reestablished, name and room were sent again
+    Reconnected,
+    Response(Response),
+    /// Connection dropped, client keeps trying to reconnect
+    Disconnected,
+}
+
+/// Connection to an Iuro server, closed when dropped
+pub struct Client {
+    commands: mpsc::Sender<Command>,
+    events: mpsc::UnboundedReceiver<Event>,
+    task: JoinHandle<()>,
+}
+
+impl Client {
+    /// Connects to `url`, like `ws://localhost:8080/ws/`, errors if the first attempt fails
+    pub async fn connect(url: &str) -> Result<Self, ClientError> {
+        let (socket, _) = connect_async(url).await?;
+        let (commands, receiver) = mpsc::channel(QUEUE_SIZE);
+        let (sender, events) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run(url.to_owned(), socket, receiver, sender));
+
+        Ok(Self {
+            commands,
+            events,
+            task,
+        })
+    }
+
+    /// Sends any command, responses arrive as events
+    pub async fn send(&self, command: Command) -> Result<(), ClientError> {
+        self.commands
+            .send(command)
+            .await
+            .map_err(|_| ClientError::Closed)
+    }
+
+    pub async fn name(&self, name: impl Into<String>) -> Result<(), ClientError> {
+        self.send(Command::Name(name.into())).await
+    }
+
+    pub async fn list_rooms(&self) -> Result<(), ClientError> {
+        self.send(Command::ListRooms).await
+    }
+
+    /// Joins room, creating it if needed
+    pub async fn join(&self, room: impl Into<String>) -> Result<(), ClientError> {
+        self.send(Command::Join(room.into())).await
+    }
+
+    /// Sends message to everybody in the room
+    pub async fn chat(&self, msg: impl Into<String>) -> Result<(), ClientError> {
+        self.send(Command::Message(msg.into())).await
+    }
+
+    /// Submits input to the running game
+    pub async fn submit(&self, input: GameInput) -> Result<(), ClientError> {
+        self.send(Command::Game(input)).await
+    }
+
+    /// Waits for the next event, `None` if the connection is gone for good
+    pub async fn next_event(&mut self) -> Option<Event> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Why a connection ended
+enum Closed {
+    /// Client was dropped, nothing else must be done
+    Client,
+    /// Connection dropped, must reconnect
+    Server,
+}
+
+/// Relays commands and responses, reconnecting whenever the connection drops
+async fn run(
+    url: String,
+    mut socket: Socket,
+    mut commands: mpsc::Receiver<Command>,
+    events: mpsc::UnboundedSender<Event>,
+) {
+    // Resent after reconnecting, so the server puts the user back where it was
+    let mut resume = Resume::default();
+
+    loop {
+        if let Closed::Client = relay(&mut socket, &mut commands, &events, &mut resume).await {
+            let _ = socket.close(None).await;
+            return;
+        }
+        let _ = events.send(Event::Disconnected);
+
+        socket = loop {
+            time::sleep(RECONNECT_DELAY).await;
+            match connect_async(url.as_str()).await {
+                Ok((socket, _)) => break socket,
+                Err(err) => debug!("Unable to reconnect: {}", err),
+            }
+        };
+        for command in resume.commands() {
+            if let Err(err) = send(&mut socket, &command).await {
+                debug!("Unable to resume: {}", err);
+            }
+        }
+        let _ = events.send(Event::Reconnected);
+    }
+}
+
+/// Name and room of the user, once they were sent
+#[derive(Default)]
+struct Resume {
+    name: Option<String>,
+    room: Option<String>,
+}
+
+impl Resume {
+    fn observe(&mut self, command: &Command) {
+        match command {
+            Command::Name(name) => self.name = Some(name.clone()),
+            Command::Join(room) => self.room = Some(room.clone()),
+            _ => (),
+        }
+    }
+
+    fn commands(&self) -> Vec<Command> {
+        let name = self.name.clone().map(Command::Name);
+        let room = self.room.clone().map(Command::Join);
+        name.into_iter().chain(room).collect()
+    }
+}
+
+async fn relay(
+    socket: &mut Socket,
+    commands: &mut mpsc::Receiver<Command>,
+    events: &mpsc::UnboundedSender<Event>,
+    resume: &mut Resume,
+) -> Closed {
+    let mut heartbeat = time::interval(HEARTBEAT_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = heartbeat.tick() => {
+                // Server still understands the browser's mocked ping
+                if socket.send(Message::Binary(vec![0x09])).await.is_err() {
+                    return Closed::Server;
+                }
+            }
+            command = commands.recv() => {
+                let command = match command {
+                    Some(command) => command,
+                    None => return Closed::Client,
+                };
+                resume.observe(&command);
+                if send(socket, &command).await.is_err() {
+                    return Closed::Server;
+                }
+            }
+            msg = socket.next() => match msg {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                    Ok(response) => {
+                        if events.send(Event::Response(response)).is_err() {
+                            return Closed::Client;
+                        }
+                    }
+                    Err(err) => warn!("Unable to parse response {}: {}", text, err),
+                },
+                // Pings are answered by tungstenite itself
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Closed::Server,
+                Some(Ok(_)) => (),
+            },
+        }
+    }
+}
+
+async fn send(socket: &mut Socket, command: &Command) -> Result<(), tungstenite::Error> {
+    // Serializing a command never fails
+    let text = serde_json::to_string(command).unwrap_or_default();
+    socket.send(Message::Text(text)).await
+}
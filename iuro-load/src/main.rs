@@ -0,0 +1,116 @@
+//! Load generator, spawns simulated clients that fill rooms, chat and play
+//!
+//! `iuro-load [--url ws://localhost:8080/ws/] [--clients 100] [--room-size 4] [--chat-rate 0.2]
+//! [--play-rate 2] [--probe-rate 1] [--duration 60]`, rates are per client per second.
+//!
+//! Reports percentiles of command round-trips, measured with `ListRooms`, of chat broadcasts
+//! reaching each user of the room, and every error the server sent.
+
+mod player;
+mod stats;
+
+use stats::Stats;
+use std::{
+    process,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How fast clients connect, so the server isn't hit by every handshake at once
+const CONNECT_INTERVAL: Duration = Duration::from_millis(5);
+
+pub struct Options {
+    url: String,
+    clients: usize,
+    /// Users put in each room, the server starts playing once a room has 4
+    room_size: usize,
+    chat_rate: f64,
+    play_rate: f64,
+    probe_rate: f64,
+    duration: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            url: "ws://localhost:8080/ws/".to_owned(),
+            clients: 100,
+            room_size: 4,
+            chat_rate: 0.2,
+            play_rate: 2.,
+            probe_rate: 1.,
+            duration: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value of {}", flag))?;
+            match flag.as_str() {
+                "--url" => options.url = value,
+                "--clients" => options.clients = parse(&flag, &value)?,
+                "--room-size" => options.room_size = parse(&flag, &value)?,
+                "--chat-rate" => options.chat_rate = parse(&flag, &value)?,
+                "--play-rate" => options.play_rate = parse(&flag, &value)?,
+                "--probe-rate" => options.probe_rate = parse(&flag, &value)?,
+                "--duration" => options.duration = Duration::from_secs(parse(&flag, &value)?),
+                _ => return Err(format!("Unknown option {}", flag)),
+            }
+        }
+        if options.room_size == 0 {
+            return Err("Rooms must fit at least one client".to_owned());
+        }
+        Ok(options)
+    }
+}
+
+fn parse<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value of {}: {}", flag, value))
+}
+
+#[tokio::main]
+async fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => Arc::new(options),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+    let stats = Arc::new(Mutex::new(Stats::new()));
+    let start = Instant::now();
+    println!(
+        "Spawning {} clients in {} rooms for {}s",
+        options.clients,
+        options.clients.div_ceil(options.room_size),
+        options.duration.as_secs()
+    );
+
+    let mut clients = Vec::with_capacity(options.clients);
+    for index in 0..options.clients {
+        // Every client runs for the whole duration, counted from when it connects
+        let deadline = Instant::now() + options.duration;
+        clients.push(tokio::spawn(player::run(
+            index,
+            options.clone(),
+            stats.clone(),
+            start,
+            deadline,
+        )));
+        tokio::time::sleep(CONNECT_INTERVAL).await;
+    }
+    for client in clients {
+        let _ = client.await;
+    }
+
+    let stats = stats.lock().unwrap_or_else(|err| err.into_inner());
+    stats.report(start.elapsed());
+}
@@ -0,0 +1,295 @@
+use crate::{stats::Stats, Options};
+use iuro_client::{
+    types::{
+        BoardView, Command, DilemmaChoice, Game, GameInput, Response, RockPapiuroScissorInput,
+    },
+    Client, Event,
+};
+use rand::{random, seq::SliceRandom, thread_rng, Rng};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::time::{self, Interval};
+
+/// Prefix of chat messages carrying when they were sent
+const CHAT_TAG: &str = "load@";
+/// Most characters sent in a single `TypingRace` update
+const TYPING_CHUNK: usize = 8;
+
+/// Simulated user, chatting, playing and probing the server until `deadline`
+pub async fn run(
+    index: usize,
+    options: Arc<Options>,
+    stats: Arc<Mutex<Stats>>,
+    start: Instant,
+    deadline: Instant,
+) {
+    let mut client = match Client::connect(&options.url).await {
+        Ok(client) => client,
+        Err(_) => {
+            lock(&stats).failed += 1;
+            return;
+        }
+    };
+    lock(&stats).connected += 1;
+
+    let name = format!("load-{}", index);
+    let room = format!("load-{}", index / options.room_size);
+    let mut player = Player::new(name.clone(), stats.clone(), start);
+    player.send(&client, Command::Name(name)).await;
+    player.send(&client, Command::Join(room)).await;
+
+    let mut chat = interval(options.chat_rate);
+    let mut play = interval(options.play_rate);
+    let mut probe = interval(options.probe_rate);
+    let end = time::sleep_until(deadline.into());
+    tokio::pin!(end);
+
+    loop {
+        tokio::select! {
+            _ = &mut end => break,
+            event = client.next_event() => match event {
+                Some(event) => player.handle(event),
+                None => break,
+            },
+            _ = tick(&mut chat) => {
+                let sent = start.elapsed().as_micros();
+                let msg = format!("{}{}", CHAT_TAG, sent);
+                player.send(&client, Command::Message(msg)).await;
+            }
+            _ = tick(&mut play) => {
+                if let Some(input) = player.input() {
+                    player.send(&client, Command::Game(input)).await;
+                }
+            }
+            _ = tick(&mut probe) => {
+                player.probes.push_back(Instant::now());
+                player.send(&client, Command::ListRooms).await;
+            }
+        }
+    }
+}
+
+fn lock(stats: &Mutex<Stats>) -> std::sync::MutexGuard<'_, Stats> {
+    // A panicking client doesn't invalidate what others recorded
+    stats.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Ticks `rate` times per second, offset randomly so clients don't act in lockstep
+fn interval(rate: f64) -> Option<Interval> {
+    if rate <= 0. {
+        return None;
+    }
+    let period = Duration::from_secs_f64(1. / rate);
+    let offset = period.mul_f64(random());
+    Some(time::interval_at((Instant::now() + offset).into(), period))
+}
+
+/// Waits for the next tick, forever if the action is disabled
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// What the simulated user knows about its room and the running game
+struct Player {
+    name: String,
+    stats: Arc<Mutex<Stats>>,
+    start: Instant,
+    /// When each `ListRooms` still waiting for a response was sent
+    probes: VecDeque<Instant>,
+    game: Option<Playing>,
+}
+
+struct Playing {
+    game: Game,
+    /// User's progress, like problems solved
+    progress: usize,
+    locked_in: bool,
+    /// Sequence `IuroSays` wants repeated, taken once it's sent
+    sequence: Option<Vec<u8>>,
+    /// Characters of `TypingRace` already sent
+    typed: usize,
+    boards: HashMap<usize, BoardView>,
+}
+
+impl Player {
+    fn new(name: String, stats: Arc<Mutex<Stats>>, start: Instant) -> Self {
+        Self {
+            name,
+            stats,
+            start,
+            probes: VecDeque::new(),
+            game: None,
+        }
+    }
+
+    async fn send(&self, client: &Client, command: Command) {
+        lock(&self.stats).commands += 1;
+        // Closed clients are noticed by `next_event`
+        let _ = client.send(command).await;
+    }
+
+    fn handle(&mut self, event: Event) {
+        let response = match event {
+            Event::Response(response) => response,
+            Event::Disconnected => {
+                lock(&self.stats).disconnects += 1;
+                // Responses of the old connection will never arrive
+                self.probes.clear();
+                return;
+            }
+            Event::Reconnected => return,
+        };
+
+        match response {
+            Response::Rooms(_) => {
+                if let Some(sent) = self.probes.pop_front() {
+                    let micros = sent.elapsed().as_micros() as u64;
+                    lock(&self.stats).round_trip.saturating_record(micros);
+                }
+            }
+            Response::Text(text) => {
+                let sent = text
+                    .rsplit_once(CHAT_TAG)
+                    .and_then(|(_, sent)| sent.parse::<u128>().ok());
+                if let Some(sent) = sent {
+                    let micros = self.start.elapsed().as_micros().saturating_sub(sent) as u64;
+                    lock(&self.stats).fan_out.saturating_record(micros);
+                }
+            }
+            Response::Error(err) => *lock(&self.stats).errors.entry(err).or_default() += 1,
+            Response::GameStarted(game) => {
+                lock(&self.stats).games += 1;
+                let sequence = match &game {
+                    Game::IuroSays((_, first)) => Some(first.clone()),
+                    _ => None,
+                };
+                self.game = Some(Playing {
+                    game,
+                    progress: 0,
+                    locked_in: false,
+                    sequence,
+                    typed: 0,
+                    boards: HashMap::new(),
+                });
+            }
+            Response::GameProgress(progress) => {
+                if let Some(playing) = &mut self.game {
+                    playing.progress = progress.progress.get(&self.name).copied().unwrap_or(0);
+                    playing.locked_in = progress.locked_in.contains(&self.name);
+                }
+            }
+            Response::SequenceRevealed((sequence, _)) => {
+                if let Some(playing) = &mut self.game {
+                    playing.sequence = Some(sequence);
+                }
+            }
+            Response::BoardUpdated(board) => {
+                if let Some(playing) = &mut self.game {
+                    playing.boards.insert(board.board, board);
+                }
+            }
+            Response::GameEnded(_) => self.game = None,
+            _ => (),
+        }
+    }
+
+    /// Next input to the running game, plausible but not necessarily right
+    fn input(&mut self) -> Option<GameInput> {
+        let playing = self.game.as_mut()?;
+        if playing.locked_in {
+            return None;
+        }
+        let mut rng = thread_rng();
+
+        let input = match &playing.game {
+            Game::RockPapiuroScissor => {
+                let throws = [
+                    RockPapiuroScissorInput::Rock,
+                    RockPapiuroScissorInput::Papiuro,
+                    RockPapiuroScissorInput::Scissor,
+                ];
+                GameInput::RockPapiuroScissor(*throws.choose(&mut rng)?)
+            }
+            Game::TheRightIuro(values) => GameInput::TheRightIuro(vec![*values.choose(&mut rng)?]),
+            Game::MathSprint(problems) => {
+                GameInput::MathSprint(problems.get(playing.progress)?.answer())
+            }
+            Game::IuroSays(_) => GameInput::IuroSays(playing.sequence.take()?),
+            Game::WordScramble(letters) => GameInput::WordScramble(letters.clone()),
+            Game::Majority(prompt) => {
+                GameInput::Majority(rng.gen_range(0, prompt.options.len().max(1)))
+            }
+            Game::PrisonersDilemma(_) => GameInput::PrisonersDilemma(if rng.gen() {
+                DilemmaChoice::Cooperate
+            } else {
+                DilemmaChoice::Defect
+            }),
+            Game::TypingRace(passage) => {
+                let chunk: String = passage
+                    .chars()
+                    .skip(playing.typed)
+                    .take(TYPING_CHUNK)
+                    .collect();
+                if chunk.is_empty() {
+                    return None;
+                }
+                playing.typed += chunk.chars().count();
+                GameInput::TypingRace(chunk)
+            }
+            Game::ClosestGuess(_) => GameInput::ClosestGuess(rng.gen_range(0, 1000)),
+            Game::TicTacToe(sides) | Game::ConnectFour(sides) => {
+                let (index, sides) = sides
+                    .iter()
+                    .enumerate()
+                    .find(|(_, sides)| sides.iter().flatten().any(|name| *name == self.name))?;
+                let tic_tac_toe = matches!(playing.game, Game::TicTacToe(_));
+                let board = playing.boards.get(&index);
+                // Boards are only sent after a move, the first side's first user starts
+                let next = match board {
+                    Some(board) => board.next.as_ref(),
+                    None => sides[0].first(),
+                };
+                if next != Some(&self.name) {
+                    return None;
+                }
+
+                let width = board.map_or(if tic_tac_toe { 3 } else { 7 }, |b| b.width);
+                let free: Vec<usize> = match board {
+                    Some(board) => board
+                        .cells
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, cell)| cell.is_none())
+                        .map(|(cell, _)| if tic_tac_toe { cell } else { cell % width })
+                        .collect(),
+                    None => (0..if tic_tac_toe { 9 } else { width }).collect(),
+                };
+                let choice = *free.choose(&mut rng)?;
+                if tic_tac_toe {
+                    GameInput::TicTacToe(choice)
+                } else {
+                    GameInput::ConnectFour(choice)
+                }
+            }
+            Game::ButtonMash(_) => GameInput::ButtonMash(rng.gen_range(1, 6)),
+        };
+
+        // Single answer games would only get "already locked in" errors afterwards
+        if let Game::RockPapiuroScissor
+        | Game::TheRightIuro(_)
+        | Game::Majority(_)
+        | Game::ClosestGuess(_) = playing.game
+        {
+            playing.locked_in = true;
+        }
+        Some(input)
+    }
+}
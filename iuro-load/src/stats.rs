@@ -0,0 +1,85 @@
+use hdrhistogram::Histogram;
+use std::{collections::HashMap, time::Duration};
+
+/// Highest latency tracked, slower samples are recorded as this
+const MAX_LATENCY: Duration = Duration::from_secs(60);
+
+/// Everything observed by the simulated clients, latencies in microseconds
+pub struct Stats {
+    /// Time between a command being sent and its response arriving
+    pub round_trip: Histogram<u64>,
+    /// Time between a chat message being sent and each user of the room receiving it
+    pub fan_out: Histogram<u64>,
+    /// Error messages sent by the server and how many times each was seen
+    pub errors: HashMap<String, usize>,
+    pub connected: usize,
+    /// Clients that couldn't connect at all
+    pub failed: usize,
+    pub disconnects: usize,
+    pub commands: usize,
+    pub games: usize,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        // Bounds are valid, so creating the histograms never fails
+        let histogram = || {
+            Histogram::new_with_bounds(1, MAX_LATENCY.as_micros() as u64, 3)
+                .expect("valid histogram bounds")
+        };
+        Self {
+            round_trip: histogram(),
+            fan_out: histogram(),
+            errors: HashMap::new(),
+            connected: 0,
+            failed: 0,
+            disconnects: 0,
+            commands: 0,
+            games: 0,
+        }
+    }
+
+    pub fn report(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64().max(1.);
+        println!(
+            "{} clients connected, {} failed to connect, {} disconnects",
+            self.connected, self.failed, self.disconnects
+        );
+        println!(
+            "{} commands sent ({:.1}/s), {} games started",
+            self.commands,
+            self.commands as f64 / secs,
+            self.games
+        );
+        print_latency("Command round-trip", &self.round_trip);
+        print_latency("Broadcast fan-out", &self.fan_out);
+
+        let mut errors: Vec<_> = self.errors.iter().collect();
+        errors.sort_by_key(|(msg, count)| (std::cmp::Reverse(**count), *msg));
+        println!(
+            "{} server errors",
+            errors.iter().map(|(_, c)| **c).sum::<usize>()
+        );
+        for (msg, count) in errors {
+            println!("  {:>8} {}", count, msg);
+        }
+    }
+}
+
+fn print_latency(name: &str, histogram: &Histogram<u64>) {
+    if histogram.is_empty() {
+        println!("{}: no samples", name);
+        return;
+    }
+    let ms = |micros: u64| micros as f64 / 1000.;
+    println!(
+        "{} ({} samples): p50 {:.2}ms, p90 {:.2}ms, p99 {:.2}ms, p99.9 {:.2}ms, max {:.2}ms",
+        name,
+        histogram.len(),
+        ms(histogram.value_at_quantile(0.5)),
+        ms(histogram.value_at_quantile(0.9)),
+        ms(histogram.value_at_quantile(0.99)),
+        ms(histogram.value_at_quantile(0.999)),
+        ms(histogram.max()),
+    );
+}
@@ -0,0 +1,501 @@
+use crossterm::event::{Event as TermEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use iuro_client::{
+    types::{
+        Answer, BoardView, BotSettings, Command, DilemmaChoice, Game, GameInput, GameKind,
+        GameResults, Phase, Reveal, RockPapiuroScissorInput, RosterEntry, TeamStanding,
+    },
+    Event,
+};
+use rand::{seq::SliceRandom, thread_rng};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// How long the right iuro is shown before the options, same as the browser client
+pub const MEMORIZE_TIME: Duration = Duration::from_secs(2);
+/// How many chat lines are kept
+const CHAT_HISTORY: usize = 500;
+/// Most characters the server accepts in a single `TypingRace` update
+const MAX_CHUNK: usize = 32;
+
+const HELP: &[&str] = &[
+    "Tab switches input between chat and game, Ctrl-C quits",
+    "/join <room>, /rooms, /name <name>, /skip, /vote <n>, /pause, /resume",
+    "/teams on|off, /team <n>, /bot, /unbot <name>, /quit",
+];
+
+/// Where typed lines go
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Focus {
+    Chat,
+    Game,
+}
+
+/// Running game and everything needed to play it
+pub struct GameView {
+    pub game: Game,
+    pub started: Instant,
+    /// Options of `TheRightIuro`, shuffled once memorizing is over
+    pub options: Vec<u8>,
+    /// Sequence `IuroSays` currently wants repeated
+    pub sequence: Vec<u8>,
+    /// Last state of each board, by board index
+    pub boards: HashMap<usize, BoardView>,
+    pub progress: HashMap<String, usize>,
+    pub locked_in: Vec<String>,
+    /// Text typed in `TypingRace`, and how much of it was already sent
+    pub typed: (String, usize),
+    /// `ButtonMash` taps not sent yet, and all taps so far
+    pub taps: (u32, u32),
+}
+
+impl GameView {
+    fn new(game: Game) -> Self {
+        let mut options = Vec::new();
+        let mut sequence = Vec::new();
+        match &game {
+            Game::TheRightIuro(values) => {
+                options = values.clone();
+                options.shuffle(&mut thread_rng());
+            }
+            Game::IuroSays((_, first)) => sequence = first.clone(),
+            _ => (),
+        }
+
+        Self {
+            game,
+            started: Instant::now(),
+            options,
+            sequence,
+            boards: HashMap::new(),
+            progress: HashMap::new(),
+            locked_in: Vec::new(),
+            typed: (String::new(), 0),
+            taps: (0, 0),
+        }
+    }
+
+    /// Board the user plays in, with its index
+    pub fn board_of(&self, me: &str) -> Option<(usize, &[Vec<String>; 2])> {
+        let sides = match &self.game {
+            Game::TicTacToe(sides) | Game::ConnectFour(sides) => sides,
+            _ => return None,
+        };
+        sides
+            .iter()
+            .enumerate()
+            .find(|(_, sides)| sides.iter().flatten().any(|name| name == me))
+    }
+}
+
+/// Everything the client knows about the server and the user's room
+pub struct App {
+    pub name: String,
+    pub room: Option<String>,
+    pub rooms: Vec<String>,
+    pub roster: Vec<RosterEntry>,
+    pub wins: HashMap<String, usize>,
+    pub teams: Vec<TeamStanding>,
+    pub chat: Vec<String>,
+    pub input: String,
+    pub focus: Focus,
+    pub game: Option<GameView>,
+    /// Room's phase and when it ends, if it's timed
+    pub phase: Option<(Phase, Option<Instant>)>,
+    pub candidates: Vec<GameKind>,
+    pub connected: bool,
+    pub quit: bool,
+}
+
+impl App {
+    pub fn new(name: String) -> Self {
+        let mut app = Self {
+            name,
+            room: None,
+            rooms: Vec::new(),
+            roster: Vec::new(),
+            wins: HashMap::new(),
+            teams: Vec::new(),
+            chat: Vec::new(),
+            input: String::new(),
+            focus: Focus::Chat,
+            game: None,
+            phase: None,
+            candidates: Vec::new(),
+            connected: true,
+            quit: false,
+        };
+        HELP.iter().for_each(|line| app.log(*line));
+        app
+    }
+
+    pub fn log(&mut self, line: impl Into<String>) {
+        self.chat.push(line.into());
+        if self.chat.len() > CHAT_HISTORY {
+            self.chat.drain(..self.chat.len() - CHAT_HISTORY);
+        }
+    }
+
+    pub fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Response(response) => self.handle_response(response),
+            Event::Disconnected => {
+                self.connected = false;
+                self.log("Disconnected, reconnecting...");
+            }
+            Event::Reconnected => {
+                self.connected = true;
+                self.log("Reconnected");
+            }
+        }
+    }
+
+    fn handle_response(&mut self, response: iuro_client::types::Response) {
+        use iuro_client::types::Response;
+
+        match response {
+            Response::Welcome(_) => (),
+            Response::Rooms(rooms) => self.rooms = rooms,
+            Response::Text(text) => self.log(text),
+            Response::Error(err) => self.log(format!("Error: {}", err)),
+            Response::GameStarted(game) => {
+                self.log(format!("{:?} started", game.kind()));
+                self.game = Some(GameView::new(game));
+                self.focus = Focus::Game;
+            }
+            Response::GameProgress(progress) => {
+                if let Some(view) = &mut self.game {
+                    view.progress = progress.progress;
+                    view.locked_in = progress.locked_in;
+                }
+            }
+            Response::SequenceRevealed((sequence, playing)) => {
+                self.log(format!(
+                    "Repeat {} iuros, still playing: {}",
+                    sequence.len(),
+                    playing.join(", ")
+                ));
+                if let Some(view) = &mut self.game {
+                    view.sequence = sequence;
+                }
+            }
+            Response::BoardUpdated(board) => {
+                if let Some(view) = &mut self.game {
+                    view.boards.insert(board.board, board);
+                }
+            }
+            Response::NextUp(games) => self.log(format!("Next up: {}", kinds(&games))),
+            Response::Teams(teams) => self.teams = teams,
+            Response::Roster(roster) => self.roster = roster,
+            Response::PhaseChanged((phase, millis)) => {
+                let ends = millis.map(|ms| Instant::now() + Duration::from_millis(ms));
+                if let Phase::Lobby | Phase::Countdown | Phase::MatchOver = phase {
+                    self.game = None;
+                    self.candidates.clear();
+                }
+                self.phase = Some((phase, ends));
+            }
+            Response::SkipVotes((votes, needed)) => {
+                self.log(format!("{}/{} voted to skip", votes, needed))
+            }
+            Response::NextCandidates(candidates) => {
+                let list: Vec<String> = candidates
+                    .iter()
+                    .enumerate()
+                    .map(|(i, kind)| format!("{} {:?}", i, kind))
+                    .collect();
+                self.log(format!(
+                    "Vote for the next game with /vote: {}",
+                    list.join(", ")
+                ));
+                self.candidates = candidates;
+            }
+            Response::NextVotes(votes) => {
+                let list: Vec<String> = self
+                    .candidates
+                    .iter()
+                    .zip(&votes)
+                    .map(|(kind, votes)| format!("{:?} {}", kind, votes))
+                    .collect();
+                self.log(format!("Votes: {}", list.join(", ")));
+            }
+            Response::GameRevealed(reveal) => self.reveal(reveal),
+            Response::GameEnded((game, wins, results)) => {
+                self.log(format!("{} ended", game));
+                if let Some(results) = results {
+                    self.results(results);
+                }
+                self.wins = wins;
+                self.game = None;
+                self.focus = Focus::Chat;
+            }
+        }
+    }
+
+    fn reveal(&mut self, reveal: Reveal) {
+        if let Some(answer) = &reveal.answer {
+            self.log(format!("{} answer: {}", reveal.game, answer_text(answer)));
+        }
+        for player in reveal.players {
+            let input = player.input.as_ref().map(answer_text).unwrap_or_default();
+            let winner = if player.winner { " (won)" } else { "" };
+            self.log(format!(
+                "  {}: {} {} points{}",
+                player.name, input, player.points, winner
+            ));
+        }
+    }
+
+    fn results(&mut self, results: GameResults) {
+        match results {
+            GameResults::Majority(split) => {
+                let side = if split.minority_wins {
+                    "Minority"
+                } else {
+                    "Majority"
+                };
+                self.log(format!("{} won, votes: {:?}", side, split.votes));
+            }
+            GameResults::TypingRace(ranking) => {
+                for result in ranking {
+                    let time = result
+                        .time
+                        .map(|ms| format!("{}ms", ms))
+                        .unwrap_or_else(|| "didn't finish".to_owned());
+                    self.log(format!(
+                        "  {}. {} {}% {}",
+                        result.position, result.name, result.accuracy, time
+                    ));
+                }
+            }
+            GameResults::ClosestGuess(guesses) => {
+                self.log(format!("Answer was {}", guesses.answer));
+                for guess in guesses.guesses {
+                    self.log(format!(
+                        "  {}: {} (off by {})",
+                        guess.name, guess.guess, guess.distance
+                    ));
+                }
+            }
+            GameResults::TicTacToe(boards) | GameResults::ConnectFour(boards) => {
+                for board in boards {
+                    let winner = match board.winner {
+                        Some(side) => format!("side {} won", side),
+                        None => "draw".to_owned(),
+                    };
+                    self.log(format!("  Board {}: {}", board.board, winner));
+                }
+            }
+            GameResults::ButtonMash(mash) => {
+                let mut taps: Vec<_> = mash.taps.into_iter().collect();
+                taps.sort_by_key(|(_, taps)| Reverse(*taps));
+                for (name, taps) in taps {
+                    let flagged = if mash.flagged.contains(&name) {
+                        " (flagged)"
+                    } else {
+                        ""
+                    };
+                    self.log(format!("  {}: {} taps{}", name, taps, flagged));
+                }
+            }
+        }
+    }
+
+    /// Sends batched taps and typed characters, called periodically
+    pub fn tick(&mut self) -> Option<Command> {
+        let view = self.game.as_mut()?;
+        if view.taps.0 > 0 {
+            let taps = std::mem::take(&mut view.taps.0);
+            return Some(Command::Game(GameInput::ButtonMash(taps)));
+        }
+
+        let (typed, sent) = &mut view.typed;
+        let chunk: String = typed[*sent..].chars().take(MAX_CHUNK).collect();
+        if chunk.is_empty() {
+            return None;
+        }
+        *sent += chunk.len();
+        Some(Command::Game(GameInput::TypingRace(chunk)))
+    }
+
+    pub fn handle_terminal(&mut self, event: TermEvent) -> Option<Command> {
+        match event {
+            TermEvent::Key(key) if key.kind != KeyEventKind::Release => self.handle_key(key),
+            _ => None,
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Command> {
+        let playing = match (&self.game, self.focus) {
+            (Some(view), Focus::Game) => Some(&view.game),
+            _ => None,
+        };
+        let mashing = matches!(playing, Some(Game::ButtonMash(_)));
+        // Typed characters are streamed, so they can't be erased
+        let racing = matches!(playing, Some(Game::TypingRace(_)));
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.quit = true;
+            }
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Chat => Focus::Game,
+                    Focus::Game => Focus::Chat,
+                };
+            }
+            KeyCode::Char(' ') if mashing => {
+                if let Some(view) = &mut self.game {
+                    view.taps.0 += 1;
+                    view.taps.1 += 1;
+                }
+            }
+            KeyCode::Char(c) if racing => {
+                if let Some(view) = &mut self.game {
+                    view.typed.0.push(c);
+                }
+            }
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Esc => self.input.clear(),
+            KeyCode::Enter => {
+                let line = std::mem::take(&mut self.input);
+                return self.submit(line.trim());
+            }
+            _ => (),
+        }
+        None
+    }
+
+    fn submit(&mut self, line: &str) -> Option<Command> {
+        if line.is_empty() {
+            return None;
+        }
+        if let Some(command) = line.strip_prefix('/') {
+            return self.command(command);
+        }
+
+        if self.focus == Focus::Game {
+            if let Some(view) = &self.game {
+                return match parse_input(view, &self.name, line) {
+                    Ok(input) => Some(Command::Game(input)),
+                    Err(hint) => {
+                        self.log(hint);
+                        None
+                    }
+                };
+            }
+        }
+        // Server doesn't echo messages back to their sender
+        self.log(format!("{}: {}", self.name, line));
+        Some(Command::Message(line.to_owned()))
+    }
+
+    fn command(&mut self, line: &str) -> Option<Command> {
+        let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+        let arg = arg.trim();
+        let number = || arg.parse::<usize>().ok();
+
+        let command = match (command, arg) {
+            ("quit", _) => {
+                self.quit = true;
+                return None;
+            }
+            ("help", _) => {
+                HELP.iter().for_each(|line| self.log(*line));
+                return None;
+            }
+            ("rooms", _) => Command::ListRooms,
+            ("join", room) if !room.is_empty() => {
+                self.room = Some(room.to_owned());
+                Command::Join(room.to_owned())
+            }
+            ("name", name) if !name.is_empty() => {
+                self.name = name.to_owned();
+                Command::Name(name.to_owned())
+            }
+            ("skip", _) => Command::Skip,
+            ("vote", _) if number().is_some() => Command::Vote(number()?),
+            ("pause", _) => Command::Pause,
+            ("resume", _) => Command::Resume,
+            ("teams", "on") => Command::TeamMode(true),
+            ("teams", "off") => Command::TeamMode(false),
+            ("team", _) if number().is_some() => Command::Team(number()?),
+            ("bot", _) => Command::AddBot(BotSettings::default()),
+            ("unbot", name) if !name.is_empty() => Command::RemoveBot(name.to_owned()),
+            _ => {
+                self.log(format!("Unknown command /{}, try /help", line));
+                return None;
+            }
+        };
+        Some(command)
+    }
+}
+
+/// Parses a line typed while playing, errors with a hint of what's expected
+fn parse_input(view: &GameView, me: &str, line: &str) -> Result<GameInput, String> {
+    let values = |hint: &str| {
+        line.split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| hint.to_owned())
+    };
+
+    let input = match &view.game {
+        Game::RockPapiuroScissor => GameInput::RockPapiuroScissor(match line {
+            "r" | "rock" => RockPapiuroScissorInput::Rock,
+            "p" | "papiuro" => RockPapiuroScissorInput::Papiuro,
+            "s" | "scissor" => RockPapiuroScissorInput::Scissor,
+            _ => return Err("Throw r, p or s".to_owned()),
+        }),
+        Game::TheRightIuro(_) => GameInput::TheRightIuro(values("Type one of the iuros")?),
+        Game::MathSprint(_) => GameInput::MathSprint(parse(line, "Type the answer")?),
+        Game::IuroSays(_) => GameInput::IuroSays(values("Type the sequence, space separated")?),
+        Game::WordScramble(_) => GameInput::WordScramble(line.to_owned()),
+        Game::Majority(_) => GameInput::Majority(parse(line, "Type the option's number")?),
+        Game::PrisonersDilemma(_) => GameInput::PrisonersDilemma(match line {
+            "c" | "cooperate" => DilemmaChoice::Cooperate,
+            "d" | "defect" => DilemmaChoice::Defect,
+            _ => return Err("Choose c or d".to_owned()),
+        }),
+        Game::TypingRace(_) => return Err("Just type, characters are sent as you go".to_owned()),
+        Game::ClosestGuess(_) => GameInput::ClosestGuess(parse(line, "Type your estimate")?),
+        Game::TicTacToe(_) => GameInput::TicTacToe(parse(line, "Type the cell's number")?),
+        Game::ConnectFour(_) => GameInput::ConnectFour(parse(line, "Type the column's number")?),
+        Game::ButtonMash(_) => return Err("Mash space instead".to_owned()),
+    };
+
+    if view.board_of(me).is_none() && matches!(view.game, Game::TicTacToe(_) | Game::ConnectFour(_))
+    {
+        return Err("You aren't playing in any board".to_owned());
+    }
+    Ok(input)
+}
+
+fn parse<T: FromStr>(line: &str, hint: &str) -> Result<T, String> {
+    line.parse().map_err(|_| hint.to_owned())
+}
+
+pub fn answer_text(answer: &Answer) -> String {
+    let join = |items: Vec<String>| items.join(" ");
+    match answer {
+        Answer::Throw(throw) => format!("{:?}", throw),
+        Answer::Values(values) => join(values.iter().map(u8::to_string).collect()),
+        Answer::Number(number) => number.to_string(),
+        Answer::Numbers(numbers) => join(numbers.iter().map(i64::to_string).collect()),
+        Answer::Text(text) => text.clone(),
+        Answer::Option(option) => format!("option {}", option),
+        Answer::Choices(choices) => join(choices.iter().map(|c| format!("{:?}", c)).collect()),
+        Answer::Moves(moves) => join(moves.iter().map(usize::to_string).collect()),
+    }
+}
+
+fn kinds(games: &[GameKind]) -> String {
+    let names: Vec<String> = games.iter().map(|kind| format!("{:?}", kind)).collect();
+    names.join(", ")
+}
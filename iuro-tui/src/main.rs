@@ -0,0 +1,77 @@
+//! Terminal client to play and debug Iuro without a browser
+//!
+//! `iuro-tui [url] [name]`, connects to `ws://localhost:8080/ws/` by default.
+
+mod app;
+mod ui;
+
+use app::App;
+use crossterm::{
+    event,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+    ExecutableCommand,
+};
+use iuro_client::Client;
+use ratatui::{backend::CrosstermBackend, Terminal};
+use std::{error::Error, io, thread, time::Duration};
+use tokio::{sync::mpsc, time};
+
+/// How often the screen is redrawn and batched inputs are sent
+const TICK: Duration = Duration::from_millis(250);
+
+type Tui = Terminal<CrosstermBackend<io::Stdout>>;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    let url = args
+        .next()
+        .unwrap_or_else(|| "ws://localhost:8080/ws/".to_owned());
+    let name = args.next().unwrap_or_else(|| "tui".to_owned());
+
+    let mut client = Client::connect(&url).await?;
+    client.name(name.as_str()).await?;
+    client.list_rooms().await?;
+
+    terminal::enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    let mut tui = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let result = run(&mut tui, &mut client, App::new(name)).await;
+
+    terminal::disable_raw_mode()?;
+    io::stdout().execute(LeaveAlternateScreen)?;
+    result
+}
+
+async fn run(tui: &mut Tui, client: &mut Client, mut app: App) -> Result<(), Box<dyn Error>> {
+    // Crossterm's reads block, so they get their own thread
+    let (sender, mut keys) = mpsc::unbounded_channel();
+    thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    let mut tick = time::interval(TICK);
+
+    while !app.quit {
+        tui.draw(|frame| ui::draw(frame, &app))?;
+
+        let command = tokio::select! {
+            event = client.next_event() => match event {
+                Some(event) => {
+                    app.handle_event(event);
+                    None
+                }
+                None => break,
+            },
+            Some(event) = keys.recv() => app.handle_terminal(event),
+            _ = tick.tick() => app.tick(),
+        };
+        if let Some(command) = command {
+            client.send(command).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,351 @@
+use crate::app::{App, Focus, GameView, MEMORIZE_TIME};
+use iuro_client::types::{grid_cells, BoardView, Estimate, Game, MathOperator};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+use std::time::Instant;
+
+/// Pieces of each side, for both board games
+const PIECES: [&str; 2] = ["X", "O"];
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(3),
+        ])
+        .split(frame.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(30), Constraint::Min(0)])
+        .split(rows[0]);
+    let sidebar = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(columns[0]);
+    let main = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(columns[1]);
+
+    draw_rooms(frame, app, sidebar[0]);
+    draw_roster(frame, app, sidebar[1]);
+    draw_game(frame, app, main[0]);
+    draw_chat(frame, app, main[1]);
+    draw_status(frame, app, rows[1]);
+    draw_input(frame, app, rows[2]);
+}
+
+fn block(title: &str, focused: bool) -> Block<'_> {
+    let style = if focused {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(style)
+}
+
+fn draw_rooms(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .rooms
+        .iter()
+        .map(|room| {
+            let style = if app.room.as_ref() == Some(room) {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(room.as_str()).style(style)
+        })
+        .collect();
+    frame.render_widget(List::new(items).block(block("Rooms", false)), area);
+}
+
+fn draw_roster(frame: &mut Frame, app: &App, area: Rect) {
+    let mut items: Vec<ListItem> = app
+        .roster
+        .iter()
+        .map(|entry| {
+            let mut text = format!(
+                "{} {}",
+                entry.name,
+                app.wins.get(&entry.name).copied().unwrap_or(0)
+            );
+            if let Some(team) = entry.team {
+                text.push_str(&format!(" t{}", team));
+            }
+            if let Some(rtt) = entry.rtt {
+                text.push_str(&format!(" {}ms", rtt));
+            }
+            if entry.bot {
+                text.push_str(" bot");
+            }
+            let style = if entry.name == app.name {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(text).style(style)
+        })
+        .collect();
+    items.extend(
+        app.teams.iter().map(|standing| {
+            ListItem::new(format!("Team {}: {} wins", standing.team, standing.wins))
+        }),
+    );
+    frame.render_widget(List::new(items).block(block("Roster", false)), area);
+}
+
+fn draw_game(frame: &mut Frame, app: &App, area: Rect) {
+    let focused = app.focus == Focus::Game;
+    let (title, lines) = match &app.game {
+        Some(view) => (
+            format!("{:?}", view.game.kind()),
+            game_lines(view, &app.name),
+        ),
+        None => ("Game".to_owned(), vec![Line::from("Waiting for a game")]),
+    };
+    let paragraph = Paragraph::new(lines)
+        .block(block(&title, focused))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
+
+fn game_lines(view: &GameView, me: &str) -> Vec<Line<'static>> {
+    let mut lines: Vec<Line> = match &view.game {
+        Game::RockPapiuroScissor => vec![Line::from("Throw r (rock), p (papiuro) or s (scissor)")],
+        Game::TheRightIuro(values) => {
+            if view.started.elapsed() < MEMORIZE_TIME {
+                let right = values.first().copied().unwrap_or_default();
+                vec![Line::from(format!("Memorize this iuro: {}", right))]
+            } else {
+                vec![
+                    Line::from("Which one was it?"),
+                    Line::from(values_text(&view.options)),
+                ]
+            }
+        }
+        Game::MathSprint(problems) => {
+            let solved = view.progress.get(me).copied().unwrap_or(0);
+            match problems.get(solved) {
+                Some(problem) => {
+                    let operator = match problem.operator {
+                        MathOperator::Add => "+",
+                        MathOperator::Subtract => "-",
+                        MathOperator::Multiply => "*",
+                    };
+                    vec![Line::from(format!(
+                        "Problem {}/{}: {} {} {} = ?",
+                        solved + 1,
+                        problems.len(),
+                        problem.lhs,
+                        operator,
+                        problem.rhs
+                    ))]
+                }
+                None => vec![Line::from("All problems solved")],
+            }
+        }
+        Game::IuroSays((pool, _)) => vec![
+            Line::from(format!("Iuros: {}", values_text(pool))),
+            Line::from(format!("Repeat: {}", values_text(&view.sequence))),
+        ],
+        Game::WordScramble(letters) => vec![Line::from(format!("Unscramble: {}", letters))],
+        Game::Majority(prompt) => {
+            let mut lines = vec![Line::from(prompt.question.clone())];
+            lines.extend(
+                prompt
+                    .options
+                    .iter()
+                    .enumerate()
+                    .map(|(i, option)| Line::from(format!("{}. {}", i, option))),
+            );
+            lines
+        }
+        Game::PrisonersDilemma(pairs) => {
+            let opponent = pairs.iter().find_map(|(a, b)| {
+                if a == me {
+                    Some(b.clone())
+                } else if b == me {
+                    Some(a.clone())
+                } else {
+                    None
+                }
+            });
+            match opponent {
+                Some(opponent) => vec![Line::from(format!(
+                    "Against {}: c (cooperate) or d (defect)",
+                    opponent
+                ))],
+                None => vec![Line::from("You sit this one out")],
+            }
+        }
+        Game::TypingRace(passage) => typing_lines(passage, &view.typed.0),
+        Game::ClosestGuess(Estimate::Question(question)) => vec![Line::from(question.clone())],
+        Game::ClosestGuess(Estimate::Grid {
+            seed,
+            width,
+            height,
+            density,
+        }) => {
+            let cells: Vec<bool> = grid_cells(*seed, *width, *height, *density).collect();
+            let mut lines = vec![Line::from("How many cells are filled?")];
+            lines.extend(cells.chunks(usize::from(*width)).map(|row| {
+                let row: String = row
+                    .iter()
+                    .map(|filled| if *filled { '#' } else { '.' })
+                    .collect();
+                Line::from(row)
+            }));
+            lines
+        }
+        Game::TicTacToe(_) | Game::ConnectFour(_) => board_lines(view, me),
+        Game::ButtonMash(millis) => vec![
+            Line::from(format!("Mash space for {}s!", millis / 1000)),
+            Line::from(format!("{} taps", view.taps.1)),
+        ],
+    };
+
+    let mut others: Vec<String> = view
+        .progress
+        .iter()
+        .map(|(name, progress)| format!("{} {}", name, progress))
+        .collect();
+    others.sort();
+    if !others.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!("Progress: {}", others.join(", "))));
+    }
+    if !view.locked_in.is_empty() {
+        lines.push(Line::from(format!(
+            "Locked in: {}",
+            view.locked_in.join(", ")
+        )));
+    }
+    lines
+}
+
+fn values_text(values: &[u8]) -> String {
+    let values: Vec<String> = values.iter().map(u8::to_string).collect();
+    values.join(" ")
+}
+
+/// Passage with what was typed highlighted, typos in red
+fn typing_lines(passage: &str, typed: &str) -> Vec<Line<'static>> {
+    let mut typed = typed.chars();
+    let spans = passage
+        .chars()
+        .map(|expected| {
+            let style = match typed.next() {
+                Some(c) if c == expected => Style::default().fg(Color::Green),
+                Some(_) => Style::default().fg(Color::Red),
+                None => Style::default().add_modifier(Modifier::DIM),
+            };
+            Span::styled(expected.to_string(), style)
+        })
+        .collect::<Vec<_>>();
+    vec![Line::from(spans)]
+}
+
+/// Board the user plays in, with cells numbered in TicTacToe and columns in ConnectFour
+fn board_lines(view: &GameView, me: &str) -> Vec<Line<'static>> {
+    let (index, sides) = match view.board_of(me) {
+        Some(board) => board,
+        None => return vec![Line::from("You sit this one out")],
+    };
+    let tic_tac_toe = matches!(view.game, Game::TicTacToe(_));
+    let empty = || {
+        let (width, height) = if tic_tac_toe { (3, 3) } else { (7, 6) };
+        BoardView {
+            board: index,
+            width,
+            cells: vec![None; width * height],
+            next: sides[0].first().cloned(),
+            winner: None,
+        }
+    };
+    let board = view.boards.get(&index).cloned().unwrap_or_else(empty);
+
+    let mut lines = vec![Line::from(format!(
+        "{} vs {}",
+        sides[0].join(", "),
+        sides[1].join(", ")
+    ))];
+    if !tic_tac_toe {
+        let header: Vec<String> = (0..board.width).map(|column| column.to_string()).collect();
+        lines.push(Line::from(header.join(" ")));
+    }
+    for (row, cells) in board.cells.chunks(board.width).enumerate() {
+        let cells: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(column, cell)| match cell {
+                Some(side) => PIECES[usize::from(*side) % 2].to_owned(),
+                None if tic_tac_toe => (row * board.width + column).to_string(),
+                None => ".".to_owned(),
+            })
+            .collect();
+        lines.push(Line::from(cells.join(" ")));
+    }
+    match &board.next {
+        Some(next) if next == me => lines.push(Line::from("Your turn")),
+        Some(next) => lines.push(Line::from(format!("{}'s turn", next))),
+        None => (),
+    }
+    lines
+}
+
+fn draw_chat(frame: &mut Frame, app: &App, area: Rect) {
+    let height = usize::from(area.height.saturating_sub(2));
+    let start = app.chat.len().saturating_sub(height);
+    let lines: Vec<Line> = app.chat[start..]
+        .iter()
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    let focused = app.focus == Focus::Chat;
+    frame.render_widget(Paragraph::new(lines).block(block("Chat", focused)), area);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let mut status = vec![match &app.room {
+        Some(room) => format!("{} in {}", app.name, room),
+        None => format!("{}, /join a room", app.name),
+    }];
+    if let Some((phase, ends)) = &app.phase {
+        let left = ends.map(|ends| ends.saturating_duration_since(Instant::now()).as_secs());
+        status.push(match left {
+            Some(left) => format!("{:?} {}s", phase, left),
+            None => format!("{:?}", phase),
+        });
+    }
+    if !app.connected {
+        status.push("disconnected".to_owned());
+    }
+    status.push("Tab: chat/game, /help".to_owned());
+    frame.render_widget(Paragraph::new(status.join(" | ")), area);
+}
+
+fn draw_input(frame: &mut Frame, app: &App, area: Rect) {
+    let title = match app.focus {
+        Focus::Chat => "Message",
+        Focus::Game => "Play",
+    };
+    frame.render_widget(
+        Paragraph::new(app.input.as_str()).block(block(title, false)),
+        area,
+    );
+    let width = u16::try_from(app.input.chars().count()).unwrap_or(u16::MAX);
+    frame.set_cursor(
+        (area.x + 1 + width).min(area.right().saturating_sub(2)),
+        area.y + 1,
+    );
+}
@@ -0,0 +1,146 @@
+// Generated from the server's protocol types, don't edit it by hand
+
+/**
+ * Value users submitted, or the correct one
+ */
+export type Answer = { Throw: RockPapiuroScissorInput } | { Values: number[] } | { Number: number } | { Numbers: number[] } | { Text: string } | { Option: number } | { Choices: DilemmaChoice[] } | { Moves: number[] };
+
+/**
+ * Board state sent to users after every move
+ */
+export type BoardView = { board: number; cells: (number | null)[]; next?: string | null; width: number; winner?: number | null };
+
+/**
+ * Strategies a bot plays with, chosen by the host when adding it
+ */
+export type BotSettings = { recall?: number; rock_papiuro_scissor?: ThrowStrategy };
+
+/**
+ * Commands sent from client (to be deserialized)
+ */
+export type Command = "ListRooms" | { Join: string } | { Name: string } | { Message: string } | { TeamMode: boolean } | { Team: number } | { TeamMessage: string } | { Playlist: Playlist } | "Pause" | "Resume" | { Settings: MatchSettings } | "Skip" | { Vote: number } | { AddBot: BotSettings } | { RemoveBot: string } | { Game: GameInput };
+
+/**
+ * Input options for `PrisonersDilemma`
+ */
+export type DilemmaChoice = "Cooperate" | "Defect";
+
+/**
+ * What users must estimate
+ */
+export type Estimate = { Question: string } | { Grid: { density: number; height: number; seed: number; width: number } };
+
+/**
+ * Enumerates available games
+ */
+export type Game = "RockPapiuroScissor" | { TheRightIuro: number[] } | { MathSprint: MathProblem[] } | { IuroSays: [number[], number[]] } | { WordScramble: string } | { Majority: Prompt } | { PrisonersDilemma: ([string, string])[] } | { TypingRace: string } | { ClosestGuess: Estimate } | { TicTacToe: string[][][] } | { ConnectFour: string[][][] } | { ButtonMash: number };
+
+/**
+ * Game input sent from user
+ */
+export type GameInput = { RockPapiuroScissor: RockPapiuroScissorInput } | { TheRightIuro: number[] } | { MathSprint: number } | { IuroSays: number[] } | { WordScramble: string } | { Majority: number } | { PrisonersDilemma: DilemmaChoice } | { TypingRace: string } | { ClosestGuess: number } | { TicTacToe: number } | { ConnectFour: number } | { ButtonMash: number };
+
+/**
+ * Identifies each game, without its data
+ */
+export type GameKind = "RockPapiuroScissor" | "TheRightIuro" | "MathSprint" | "IuroSays" | "WordScramble" | "Majority" | "PrisonersDilemma" | "TypingRace" | "ClosestGuess" | "TicTacToe" | "ConnectFour" | "ButtonMash";
+
+/**
+ * Running game's progress, sent after every accepted input
+ */
+export type GameProgress = { game: string; locked_in: string[]; progress: Record<string, number> };
+
+/**
+ * Game specific results, sent along with the wins when a game ends
+ */
+export type GameResults = { Majority: VoteSplit } | { TypingRace: TypingResult[] } | { ClosestGuess: GuessResults } | { TicTacToe: BoardView[] } | { ConnectFour: BoardView[] } | { ButtonMash: MashResults };
+
+/**
+ * User's guess and how far it was from the answer
+ */
+export type GuessResult = { distance: number; guess: number; name: string };
+
+/**
+ * True value and every guess, closest first, sent with the game results
+ */
+export type GuessResults = { answer: number; guesses: GuessResult[] };
+
+/**
+ * Taps of each user, sent with the game results
+ */
+export type MashResults = { flagged: string[]; taps: Record<string, number> };
+
+/**
+ * How long timed phases last and how many games a match has, set by the room's host
+ */
+export type MatchSettings = { countdown?: number; games?: number; match_over?: number; results?: number };
+
+/**
+ * Arithmetic operations a problem can have
+ */
+export type MathOperator = "Add" | "Subtract" | "Multiply";
+
+/**
+ * Arithmetic problem, the answer is never sent to the user
+ */
+export type MathProblem = { lhs: number; operator: MathOperator; rhs: number };
+
+/**
+ * What a room is doing
+ */
+export type Phase = "Lobby" | "Countdown" | "Playing" | "Results" | "Paused" | "MatchOver";
+
+/**
+ * What a user submitted and how it went
+ */
+export type PlayerReveal = { input?: Answer | null; name: string; points: number; winner: boolean };
+
+/**
+ * Which games a room plays and how they are picked, set by the room's host
+ */
+export type Playlist = { no_repeat?: boolean; order?: GameKind[]; weights?: Record<string, number> };
+
+/**
+ * Question and the options users may vote for
+ */
+export type Prompt = { options: string[]; question: string };
+
+/**
+ * All messages that can be sent to user (serialized)
+ */
+export type Response = { Welcome: number } | { Rooms: string[] } | { Text: string } | { Error: string } | { GameStarted: Game } | { GameProgress: GameProgress } | { SequenceRevealed: [number[], string[]] } | { BoardUpdated: BoardView } | { NextUp: GameKind[] } | { Teams: TeamStanding[] } | { Roster: RosterEntry[] } | { PhaseChanged: [Phase, number | null] } | { SkipVotes: [number, number] } | { NextCandidates: GameKind[] } | { NextVotes: number[] } | { GameRevealed: Reveal } | { GameEnded: [string, Record<string, number>, GameResults | null] };
+
+/**
+ * Every user's input and result in the game that just ended
+ */
+export type Reveal = { answer?: Answer | null; game: string; players: PlayerReveal[] };
+
+/**
+ * Input options for `RockPapiuroScissor`
+ */
+export type RockPapiuroScissorInput = "Rock" | "Papiuro" | "Scissor";
+
+/**
+ * User in a room and how responsive their connection is
+ */
+export type RosterEntry = { bot: boolean; name: string; rtt?: number | null; team?: number | null };
+
+/**
+ * Team's members and how many wins they have together
+ */
+export type TeamStanding = { members: string[]; team: number; wins: number };
+
+/**
+ * How bots throw in `RockPapiuroScissor`
+ */
+export type ThrowStrategy = "Random" | { Biased: RockPapiuroScissorInput } | "Adaptive";
+
+/**
+ * User's standing in the race, sent with the game results
+ */
+export type TypingResult = { accuracy: number; name: string; position: number; time?: number | null };
+
+/**
+ * How users voted, sent with the game results
+ */
+export type VoteSplit = { minority_wins: boolean; votes: number[] };
@@ -0,0 +1,169 @@
+//! Headless players the host can add to fill empty seats
+//!
+//! Bots occupy a `RoomSlot` like any user, but their slot's recipient is a `Bot` actor instead
+//! of a session. They submit inputs with `UserGameInput`, so they go through `Room::update`.
+
+use actix::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub mod rock_papiuro_scissor;
+pub mod the_right_iuro;
+
+use crate::prelude::*;
+use rock_papiuro_scissor::{AdaptiveThrow, BiasedThrow, RandomThrow};
+use the_right_iuro::Recall;
+
+/// How long bots take to answer, so users see them thinking
+const THINKING_TIME: Duration = Duration::from_millis(800);
+
+/// How a bot plays a game, each game has its own strategies
+pub trait Strategy {
+    /// Input sent once `game` starts, `None` if strategy doesn't play it
+    fn play(&mut self, game: &Game) -> Option<GameInput>;
+
+    /// Learns from every user's input once a game ends, `name` is the bot's own name
+    fn learn(&mut self, _reveal: &Reveal, _name: &str) {}
+}
+
+/// How bots throw in `RockPapiuroScissor`
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub enum ThrowStrategy {
+    /// Every throw is equally likely
+    Random,
+    /// Favors a throw, playing it half of the time
+    Biased(RockPapiuroScissorInput),
+    /// Counters the throw other users played the most
+    Adaptive,
+}
+
+/// Strategies a bot plays with, chosen by the host when adding it
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+#[serde(default)]
+pub struct BotSettings {
+    pub rock_papiuro_scissor: ThrowStrategy,
+    /// Chance of remembering the right iuro, from 0 to 1, which never misses
+    pub recall: f64,
+}
+
+impl Default for BotSettings {
+    fn default() -> Self {
+        Self {
+            rock_papiuro_scissor: ThrowStrategy::Random,
+            recall: 0.75,
+        }
+    }
+}
+
+impl BotSettings {
+    /// Errors if recall isn't a probability
+    pub fn validate(&self) -> Result<(), IuroError> {
+        if !(0.0..=1.0).contains(&self.recall) {
+            return Err(IuroError::InvalidInput);
+        }
+        Ok(())
+    }
+
+    fn strategies(&self) -> Vec<Box<dyn Strategy>> {
+        let throw: Box<dyn Strategy> = match self.rock_papiuro_scissor {
+            ThrowStrategy::Random => Box::new(RandomThrow),
+            ThrowStrategy::Biased(favorite) => Box::new(BiasedThrow(favorite)),
+            ThrowStrategy::Adaptive => Box::new(AdaptiveThrow::default()),
+        };
+        vec![throw, Box::new(Recall(self.recall))]
+    }
+}
+
+/// Plays for a bot's `RoomSlot`
+///
+/// Games it has no strategy for end by their time limit, untimed ones may wait for it forever so
+/// it votes to skip them, which only skips boards no user that isn't a bot is playing
+pub struct Bot {
+    id: usize,
+    name: String,
+    room: String,
+    addr: Addr<IuroServer>,
+    strategies: Vec<Box<dyn Strategy>>,
+}
+
+impl Bot {
+    pub fn new(
+        id: usize,
+        name: String,
+        room: String,
+        settings: &BotSettings,
+        addr: Addr<IuroServer>,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            room,
+            addr,
+            strategies: settings.strategies(),
+        }
+    }
+}
+
+/// Stops by itself once its slot is dropped, as nothing else holds its address
+impl Actor for Bot {
+    type Context = Context<Self>;
+}
+
+impl Handler<Broadcast> for Bot {
+    type Result = ();
+
+    fn handle(&mut self, msg: Broadcast, ctx: &mut Self::Context) -> Self::Result {
+        match msg {
+            Broadcast::GameStarted(game) => {
+                let input = self
+                    .strategies
+                    .iter_mut()
+                    .find_map(|strategy| strategy.play(&game));
+
+                if let Some(input) = input {
+                    ctx.run_later(THINKING_TIME, move |act, _| {
+                        // Inputs sent after the game ended are simply discarded
+                        act.addr.do_send(UserGameInput {
+                            id: act.id,
+                            room: act.room.clone(),
+                            input,
+                        });
+                    });
+                } else if game.time_limit().is_none() {
+                    self.addr.do_send(VoteSkip {
+                        id: self.id,
+                        room: self.room.clone(),
+                    });
+                }
+            }
+            Broadcast::GameRevealed(reveal) => {
+                for strategy in &mut self.strategies {
+                    strategy.learn(&reveal, &self.name);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recall_must_be_a_probability() {
+        let settings = |recall| BotSettings {
+            recall,
+            ..BotSettings::default()
+        };
+        assert!(settings(0.).validate().is_ok());
+        assert!(settings(1.).validate().is_ok());
+        assert!(matches!(
+            settings(1.5).validate(),
+            Err(IuroError::InvalidInput)
+        ));
+        assert!(settings(-0.1).validate().is_err());
+        assert!(settings(f64::NAN).validate().is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use super::Strategy;
+use crate::games::reveal::Answer;
+use crate::prelude::*;
+use rand::prelude::*;
+
+const THROWS: [RockPapiuroScissorInput; 3] = [
+    RockPapiuroScissorInput::Rock,
+    RockPapiuroScissorInput::Papiuro,
+    RockPapiuroScissorInput::Scissor,
+];
+
+/// Chance of `BiasedThrow` playing its favorite
+const BIAS: f64 = 0.5;
+
+fn random_throw() -> RockPapiuroScissorInput {
+    THROWS[thread_rng().gen_range(0, THROWS.len())]
+}
+
+/// Every throw is equally likely
+pub struct RandomThrow;
+
+impl Strategy for RandomThrow {
+    fn play(&mut self, game: &Game) -> Option<GameInput> {
+        match game {
+            Game::RockPapiuroScissor => Some(GameInput::RockPapiuroScissor(random_throw())),
+            _ => None,
+        }
+    }
+}
+
+/// Plays its favorite throw half of the time, random otherwise
+pub struct BiasedThrow(pub RockPapiuroScissorInput);
+
+impl Strategy for BiasedThrow {
+    fn play(&mut self, game: &Game) -> Option<GameInput> {
+        match game {
+            Game::RockPapiuroScissor if thread_rng().gen_bool(BIAS) => {
+                Some(GameInput::RockPapiuroScissor(self.0))
+            }
+            Game::RockPapiuroScissor => Some(GameInput::RockPapiuroScissor(random_throw())),
+            _ => None,
+        }
+    }
+}
+
+/// Counters the throw other users played the most, random until it saw any
+#[derive(Default)]
+pub struct AdaptiveThrow {
+    /// How many times each of `THROWS` was seen
+    seen: [usize; 3],
+}
+
+impl Strategy for AdaptiveThrow {
+    fn play(&mut self, game: &Game) -> Option<GameInput> {
+        if !matches!(game, Game::RockPapiuroScissor) {
+            return None;
+        }
+
+        let favorite = THROWS
+            .iter()
+            .zip(&self.seen)
+            .filter(|(_, count)| **count > 0)
+            .max_by_key(|(_, count)| **count)
+            .map(|(throw, _)| *throw);
+        let throw = favorite
+            .and_then(|favorite| THROWS.iter().copied().find(|throw| throw.beats(favorite)))
+            .unwrap_or_else(random_throw);
+        Some(GameInput::RockPapiuroScissor(throw))
+    }
+
+    fn learn(&mut self, reveal: &Reveal, name: &str) {
+        let throws = reveal
+            .players
+            .iter()
+            .filter(|player| player.name != name)
+            .filter_map(|player| match player.input {
+                Some(Answer::Throw(throw)) => THROWS.iter().position(|t| *t == throw),
+                _ => None,
+            });
+        for index in throws {
+            self.seen[index] += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::reveal::PlayerReveal;
+    use std::borrow::Cow;
+
+    fn throw(strategy: &mut impl Strategy) -> RockPapiuroScissorInput {
+        match strategy.play(&Game::RockPapiuroScissor) {
+            Some(GameInput::RockPapiuroScissor(throw)) => throw,
+            other => panic!("expected a throw, got {:?}", other),
+        }
+    }
+
+    fn reveal(throws: &[(&str, RockPapiuroScissorInput)]) -> Reveal {
+        Reveal {
+            game: Cow::Borrowed("RockPapiuroScissor"),
+            answer: None,
+            players: throws
+                .iter()
+                .map(|(name, throw)| PlayerReveal {
+                    name: (*name).to_owned(),
+                    input: Some(Answer::Throw(*throw)),
+                    points: 0,
+                    winner: false,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn only_plays_rock_papiuro_scissor() {
+        let game = Game::TicTacToe(Vec::new());
+        assert!(RandomThrow.play(&game).is_none());
+        assert!(BiasedThrow(RockPapiuroScissorInput::Rock)
+            .play(&game)
+            .is_none());
+        assert!(AdaptiveThrow::default().play(&game).is_none());
+    }
+
+    #[test]
+    fn biased_throw_favors_its_throw() {
+        let mut strategy = BiasedThrow(RockPapiuroScissorInput::Scissor);
+        let favorite = (0..1000)
+            .filter(|_| throw(&mut strategy) == RockPapiuroScissorInput::Scissor)
+            .count();
+        // Favorite is thrown half of the time, plus a third of the random throws
+        assert!(favorite > 550, "favorite thrown {} times", favorite);
+    }
+
+    #[test]
+    fn adaptive_throw_counters_other_users() {
+        let mut strategy = AdaptiveThrow::default();
+        strategy.learn(
+            &reveal(&[
+                ("bot", RockPapiuroScissorInput::Papiuro),
+                ("user", RockPapiuroScissorInput::Rock),
+            ]),
+            "bot",
+        );
+        strategy.learn(
+            &reveal(&[
+                ("bot", RockPapiuroScissorInput::Papiuro),
+                ("user", RockPapiuroScissorInput::Rock),
+                ("other", RockPapiuroScissorInput::Scissor),
+            ]),
+            "bot",
+        );
+
+        // Its own throws are ignored, so rock is the most played
+        for _ in 0..10 {
+            assert_eq!(throw(&mut strategy), RockPapiuroScissorInput::Papiuro);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use super::Strategy;
+use crate::prelude::*;
+use rand::prelude::*;
+
+/// Remembers the right iuro with the given chance, 1 never misses, picks a wrong one otherwise
+pub struct Recall(pub f64);
+
+impl Strategy for Recall {
+    fn play(&mut self, game: &Game) -> Option<GameInput> {
+        let values = match game {
+            Game::TheRightIuro(values) => values,
+            _ => return None,
+        };
+
+        let mut rng = thread_rng();
+        // The right iuro is the first one, users only see it before the values are shuffled
+        let right = *values.first()?;
+        let value = if rng.gen_bool(self.0) {
+            right
+        } else {
+            values.get(1..)?.choose(&mut rng).copied().unwrap_or(right)
+        };
+        Some(GameInput::TheRightIuro(vec![value]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pick(recall: f64, values: &[u8]) -> Option<Vec<u8>> {
+        match Recall(recall).play(&Game::TheRightIuro(values.to_vec())) {
+            Some(GameInput::TheRightIuro(picked)) => Some(picked),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn recall_chooses_right_iuro() {
+        let values = [4, 8, 15, 16];
+        for _ in 0..100 {
+            assert_eq!(pick(1., &values), Some(vec![4]));
+            let wrong = pick(0., &values).expect("recall plays TheRightIuro");
+            assert_eq!(wrong.len(), 1);
+            assert!(values[1..].contains(&wrong[0]));
+        }
+    }
+
+    #[test]
+    fn recall_only_plays_the_right_iuro() {
+        assert!(pick(1., &[]).is_none());
+        assert!(Recall(1.).play(&Game::RockPapiuroScissor).is_none());
+    }
+}
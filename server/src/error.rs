@@ -7,10 +7,44 @@ pub enum IuroError {
     NoGameRunning,
     #[error("Tried to play invalid game")]
     InvalidGame,
+    #[error("Invalid input for current game")]
+    InvalidInput,
+    #[error("It's not your turn")]
+    NotYourTurn,
+    #[error("Your answer is already locked in")]
+    LockedIn,
+    #[error("Sending inputs too fast")]
+    RateLimited,
+    #[error("You were eliminated from this game")]
+    Eliminated,
+    #[error("Room is not in team mode")]
+    NoTeams,
+    #[error("Can't change teams while a game is running")]
+    GameRunning,
+    #[error("Only the room's host can do that")]
+    NotHost,
+    #[error("Playlist must have at least one game")]
+    EmptyPlaylist,
+    #[error("Playlist weights must add up to at most {}", u32::MAX)]
+    PlaylistTooHeavy,
+    #[error("No vote currently running")]
+    NoVote,
+    #[error("Room is paused")]
+    Paused,
+    #[error("Room can't be paused now")]
+    CantPause,
+    #[error("Room isn't paused")]
+    NotPaused,
+    #[error("`{0}` isn't a bot in this room")]
+    NoBot(String),
     #[error("Must join room first")]
     MustJoinRoom,
+    #[error("Protocol version {0} is not supported")]
+    UnsupportedVersion(u64),
     #[error("Unable to parse message")]
     JsonParsingFailed(#[from] serde_json::Error),
+    #[error("Unable to parse message")]
+    MessagePackParsingFailed(#[from] rmp_serde::decode::Error),
     #[error("Room `{0}` not found")]
     NoRoom(String),
     #[error("Room `{0}` is full")]
@@ -19,6 +53,8 @@ pub enum IuroError {
     AddrNotFound(usize),
     #[error("{}", internal_error(.0))]
     MailBox(#[from] actix::MailboxError),
+    #[error("{}", internal_error(.0))]
+    MessagePackEncodingFailed(#[from] rmp_serde::encode::Error),
 }
 
 fn internal_error(error: impl std::fmt::Debug) -> &'static str {
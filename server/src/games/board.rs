@@ -0,0 +1,412 @@
+//! Turn based board games, users are paired and each pair plays its own board.
+//!
+//! In team mode both teams play a single board, taking turns between teams and between members.
+
+use crate::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Board games available
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq)]
+pub enum BoardKind {
+    /// 3x3 board, input is the cell, three in a row wins
+    TicTacToe,
+    /// 7x6 board, input is the column the piece is dropped in, four in a row wins
+    ConnectFour,
+}
+
+impl BoardKind {
+    fn width(self) -> usize {
+        match self {
+            BoardKind::TicTacToe => 3,
+            BoardKind::ConnectFour => 7,
+        }
+    }
+
+    fn height(self) -> usize {
+        match self {
+            BoardKind::TicTacToe => 3,
+            BoardKind::ConnectFour => 6,
+        }
+    }
+
+    /// Pieces in a row needed to win
+    fn line(self) -> usize {
+        match self {
+            BoardKind::TicTacToe => 3,
+            BoardKind::ConnectFour => 4,
+        }
+    }
+
+    /// Cell the input places a piece in, if the move is valid
+    fn target(self, cells: &[Option<u8>], input: usize) -> Option<usize> {
+        match self {
+            BoardKind::TicTacToe => Some(input).filter(|cell| cells.get(*cell) == Some(&None)),
+            BoardKind::ConnectFour => {
+                if input >= self.width() {
+                    return None;
+                }
+                // Pieces fall to the lowest empty cell of the column, row 0 is the top one
+                (0..self.height())
+                    .rev()
+                    .map(|row| row * self.width() + input)
+                    .find(|cell| cells[*cell].is_none())
+            }
+        }
+    }
+}
+
+/// Board shared by two sides, each side may have more than one user
+#[derive(Debug)]
+pub struct BoardMatch {
+    /// Users of each side, in the order they play
+    pub sides: [Vec<usize>; 2],
+    /// Which side owns each cell, row by row
+    pub cells: Vec<Option<u8>>,
+    /// Moves already made
+    pub turn: usize,
+    /// `Some(Some(side))` if a side won, `Some(None)` for a draw
+    pub result: Option<Option<u8>>,
+}
+
+impl BoardMatch {
+    /// User that must play now, `None` if match is over
+    pub fn next(&self) -> Option<usize> {
+        if self.result.is_some() {
+            return None;
+        }
+        let side = &self.sides[self.turn % 2];
+        side.get((self.turn / 2) % side.len().max(1)).copied()
+    }
+}
+
+/// Kind of board and every match being played
+#[derive(Debug)]
+pub struct BoardState {
+    pub kind: BoardKind,
+    pub matches: Vec<BoardMatch>,
+    /// Cells, or columns, each user played, in order
+    pub moves: HashMap<usize, Vec<usize>>,
+}
+
+impl BoardState {
+    pub fn new(kind: BoardKind, sides: Vec<[Vec<usize>; 2]>) -> Self {
+        let cells = kind.width() * kind.height();
+        let matches = sides
+            .into_iter()
+            .map(|sides| BoardMatch {
+                sides,
+                cells: vec![None; cells],
+                turn: 0,
+                result: None,
+            })
+            .collect();
+
+        Self {
+            kind,
+            matches,
+            moves: HashMap::default(),
+        }
+    }
+}
+
+/// Board state sent to users after every move
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct BoardView {
+    /// Which of the room's boards this is
+    pub board: usize,
+    pub width: usize,
+    /// Which side owns each cell, row by row
+    pub cells: Vec<Option<u8>>,
+    /// User that must play now, `None` if match is over
+    pub next: Option<String>,
+    /// Side that won, `None` while playing or if it was a draw
+    pub winner: Option<u8>,
+}
+
+/// Sides of each board, with users' names
+pub fn sides(state: &BoardState, sessions: &HashMap<usize, RoomSlot>) -> Vec<[Vec<String>; 2]> {
+    let names = |ids: &Vec<usize>| {
+        ids.iter()
+            .filter_map(|id| sessions.get(id))
+            .map(|slot| slot.name.clone())
+            .collect()
+    };
+    state
+        .matches
+        .iter()
+        .map(|m| [names(&m.sides[0]), names(&m.sides[1])])
+        .collect()
+}
+
+/// Current state of one of the boards
+pub fn view(state: &BoardState, board: usize, sessions: &HashMap<usize, RoomSlot>) -> BoardView {
+    let m = &state.matches[board];
+    BoardView {
+        board,
+        width: state.kind.width(),
+        cells: m.cells.clone(),
+        next: m
+            .next()
+            .and_then(|id| sessions.get(&id))
+            .map(|slot| slot.name.clone()),
+        winner: m.result.flatten(),
+    }
+}
+
+/// Final state of every board
+pub fn views(state: &BoardState, sessions: &HashMap<usize, RoomSlot>) -> Vec<BoardView> {
+    (0..state.matches.len())
+        .map(|board| view(state, board, sessions))
+        .collect()
+}
+
+/// Possible results of a move
+pub enum Outcome {
+    /// Move was made, returns which board changed
+    Moved(usize),
+    /// Every match is over, winners already got their wins
+    Ended,
+}
+
+pub struct Update<'a> {
+    pub user_id: usize,
+    pub input: usize,
+    pub state: &'a mut BoardState,
+}
+
+impl<'a> Update<'a> {
+    pub fn consume(self, sessions: &mut HashMap<usize, RoomSlot>) -> Result<Outcome, IuroError> {
+        let Update {
+            state,
+            user_id,
+            input,
+        } = self;
+        let kind = state.kind;
+
+        let (board, current) = state
+            .matches
+            .iter_mut()
+            .enumerate()
+            .find(|(_, m)| m.sides.iter().any(|side| side.contains(&user_id)))
+            // User is sitting out
+            .ok_or(IuroError::InvalidInput)?;
+
+        if current.next() != Some(user_id) {
+            return Err(IuroError::NotYourTurn);
+        }
+
+        let cell = kind
+            .target(&current.cells, input)
+            .ok_or(IuroError::InvalidInput)?;
+        let side = (current.turn % 2) as u8;
+        current.cells[cell] = Some(side);
+        current.turn += 1;
+        state.moves.entry(user_id).or_default().push(input);
+
+        if wins(kind, &current.cells, cell) {
+            current.result = Some(Some(side));
+        } else if current.cells.iter().all(Option::is_some) {
+            current.result = Some(None);
+        }
+
+        // Some matches are still being played
+        if state.matches.iter().any(|m| m.result.is_none()) {
+            return Ok(Outcome::Moved(board));
+        }
+
+        for m in &state.matches {
+            if let Some(Some(side)) = m.result {
+                for id in &m.sides[usize::from(side)] {
+                    let slot = sessions
+                        .get_mut(id)
+                        // This should never happen
+                        .ok_or(IuroError::AddrNotFound(*id))?;
+
+                    slot.wins += 1;
+                }
+            }
+        }
+        Ok(Outcome::Ended)
+    }
+}
+
+/// Checks if the piece placed in `cell` completed a line
+fn wins(kind: BoardKind, cells: &[Option<u8>], cell: usize) -> bool {
+    let (width, height) = (kind.width() as isize, kind.height() as isize);
+    let (row, column) = (cell as isize / width, cell as isize % width);
+    let side = cells[cell];
+
+    // Horizontal, vertical and both diagonals
+    [(0, 1), (1, 0), (1, 1), (1, -1)]
+        .iter()
+        .any(|(row_step, column_step)| {
+            // Counts pieces of the same side in both directions of the line
+            let count = |sign: isize| {
+                (1..)
+                    .map(|distance| {
+                        (
+                            row + row_step * distance * sign,
+                            column + column_step * distance * sign,
+                        )
+                    })
+                    .take_while(|(r, c)| {
+                        (0..height).contains(r)
+                            && (0..width).contains(c)
+                            && cells[(r * width + c) as usize] == side
+                    })
+                    .count()
+            };
+            1 + count(1) + count(-1) >= kind.line()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sessions;
+    use actix::System;
+
+    /// Plays every `(user, input)` move, all but the last one must leave matches running
+    fn play(
+        state: &mut BoardState,
+        sessions: &mut HashMap<usize, RoomSlot>,
+        moves: &[(usize, usize)],
+    ) -> Result<Outcome, IuroError> {
+        let (last, moves) = moves.split_last().expect("at least one move");
+        for (user_id, input) in moves {
+            let update = Update {
+                user_id: *user_id,
+                input: *input,
+                state,
+            };
+            assert!(matches!(update.consume(sessions), Ok(Outcome::Moved(0))));
+        }
+        let update = Update {
+            user_id: last.0,
+            input: last.1,
+            state,
+        };
+        update.consume(sessions)
+    }
+
+    /// User 0 plays side 0 against user 1
+    fn duel(kind: BoardKind) -> BoardState {
+        BoardState::new(kind, vec![[vec![0], vec![1]]])
+    }
+
+    fn assert_side_0_wins(kind: BoardKind, moves: &[(usize, usize)]) {
+        let mut sessions = sessions(2);
+        let mut state = duel(kind);
+        let outcome = play(&mut state, &mut sessions, moves);
+        assert!(matches!(outcome, Ok(Outcome::Ended)), "{:?}", moves);
+        assert_eq!(state.matches[0].result, Some(Some(0)));
+        assert_eq!(sessions[&0].wins, 1);
+        assert_eq!(sessions[&1].wins, 0);
+    }
+
+    #[test]
+    fn tic_tac_toe_lines_win() {
+        let _system = System::new("test");
+        let kind = BoardKind::TicTacToe;
+        assert_side_0_wins(kind, &[(0, 0), (1, 3), (0, 1), (1, 4), (0, 2)]);
+        assert_side_0_wins(kind, &[(0, 0), (1, 1), (0, 3), (1, 4), (0, 6)]);
+        assert_side_0_wins(kind, &[(0, 0), (1, 1), (0, 4), (1, 2), (0, 8)]);
+        assert_side_0_wins(kind, &[(0, 2), (1, 0), (0, 4), (1, 1), (0, 6)]);
+    }
+
+    #[test]
+    fn connect_four_lines_win() {
+        let _system = System::new("test");
+        let kind = BoardKind::ConnectFour;
+        // Side 0 plays every even move, columns alternate between sides
+        let moves = |columns: &[usize]| -> Vec<(usize, usize)> {
+            columns
+                .iter()
+                .enumerate()
+                .map(|(turn, column)| (turn % 2, *column))
+                .collect()
+        };
+        assert_side_0_wins(kind, &moves(&[0, 0, 1, 1, 2, 2, 3]));
+        assert_side_0_wins(kind, &moves(&[0, 1, 0, 1, 0, 1, 0]));
+        assert_side_0_wins(kind, &moves(&[0, 1, 1, 2, 2, 3, 2, 3, 3, 6, 3]));
+        assert_side_0_wins(kind, &moves(&[6, 5, 5, 4, 4, 3, 4, 3, 3, 0, 3]));
+    }
+
+    #[test]
+    fn full_board_is_a_draw() {
+        let _system = System::new("test");
+        let mut sessions = sessions(2);
+        let mut state = duel(BoardKind::TicTacToe);
+        let moves = [
+            (0, 0),
+            (1, 1),
+            (0, 2),
+            (1, 4),
+            (0, 3),
+            (1, 5),
+            (0, 7),
+            (1, 6),
+            (0, 8),
+        ];
+        let outcome = play(&mut state, &mut sessions, &moves);
+        assert!(matches!(outcome, Ok(Outcome::Ended)));
+        assert_eq!(state.matches[0].result, Some(None));
+        assert!(sessions.values().all(|slot| slot.wins == 0));
+    }
+
+    #[test]
+    fn moves_follow_turn_order() {
+        let _system = System::new("test");
+        let mut sessions = sessions(5);
+        let mut state = duel(BoardKind::TicTacToe);
+        assert!(matches!(
+            play(&mut state, &mut sessions, &[(1, 0)]),
+            Err(IuroError::NotYourTurn)
+        ));
+        assert!(matches!(
+            play(&mut state, &mut sessions, &[(0, 0), (0, 1)]),
+            Err(IuroError::NotYourTurn)
+        ));
+        // User 4 is sitting out
+        assert!(matches!(
+            play(&mut state, &mut sessions, &[(4, 2)]),
+            Err(IuroError::InvalidInput)
+        ));
+
+        // Sides take turns, and so do the members of each side
+        let mut state = BoardState::new(BoardKind::TicTacToe, vec![[vec![0, 2], vec![1, 3]]]);
+        let mut order = Vec::new();
+        for cell in 0..5 {
+            let next = state.matches[0].next().expect("match is running");
+            order.push(next);
+            assert!(play(&mut state, &mut sessions, &[(next, cell)]).is_ok());
+        }
+        assert_eq!(order, [0, 1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn connect_four_rejects_full_and_missing_columns() {
+        let _system = System::new("test");
+        let mut sessions = sessions(2);
+        let mut state = duel(BoardKind::ConnectFour);
+        let moves: Vec<_> = (0..6).map(|turn| (turn % 2, 0)).collect();
+        assert!(matches!(
+            play(&mut state, &mut sessions, &moves),
+            Ok(Outcome::Moved(0))
+        ));
+
+        assert!(matches!(
+            play(&mut state, &mut sessions, &[(0, 0)]),
+            Err(IuroError::InvalidInput)
+        ));
+        assert!(matches!(
+            play(&mut state, &mut sessions, &[(0, 7)]),
+            Err(IuroError::InvalidInput)
+        ));
+        // Rejected moves don't pass the turn
+        assert_eq!(state.matches[0].next(), Some(0));
+        assert!(play(&mut state, &mut sessions, &[(0, 1)]).is_ok());
+    }
+}
@@ -0,0 +1,181 @@
+//! Users tap as many times as they can during a fixed window.
+//!
+//! Taps are batched by each session before reaching the server, and the server caps how many taps
+//! each user may have at any moment, excess taps are discarded and the user is flagged.
+
+use crate::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// How long users may tap
+pub const WINDOW: Duration = Duration::from_secs(10);
+/// Fastest tapping rate deemed humanly possible
+const MAX_TAPS_PER_SECOND: u32 = 20;
+/// Taps allowed above the rate, so batching doesn't cause legit taps to be discarded
+const BURST: u32 = 10;
+
+/// Taps of each user and who was flagged for tapping too fast
+#[derive(Debug)]
+pub struct ButtonMashState {
+    pub started: Instant,
+    pub taps: HashMap<usize, u32>,
+    pub flagged: HashSet<usize>,
+}
+
+impl ButtonMashState {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            taps: HashMap::default(),
+            flagged: HashSet::default(),
+        }
+    }
+}
+
+/// Taps of each user, sent with the game results
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct MashResults {
+    pub taps: HashMap<String, u32>,
+    /// Users that tapped faster than humanly possible
+    pub flagged: Vec<String>,
+}
+
+pub struct Update<'a> {
+    pub user_id: usize,
+    pub input: u32,
+    pub state: &'a mut ButtonMashState,
+}
+
+impl<'a> Update<'a> {
+    pub fn consume(self) -> Result<(), IuroError> {
+        let Update {
+            state,
+            user_id,
+            input,
+        } = self;
+
+        let elapsed = state.started.elapsed().min(WINDOW);
+        let cap = (elapsed.as_millis() as u32)
+            .saturating_mul(MAX_TAPS_PER_SECOND)
+            .checked_div(1000)
+            .unwrap_or(0)
+            .saturating_add(BURST);
+
+        let taps = state.taps.entry(user_id).or_insert(0);
+        let accepted = input.min(cap.saturating_sub(*taps));
+        *taps += accepted;
+
+        if accepted < input {
+            warn!(
+                "User {} tapped faster than allowed, discarding {} taps",
+                user_id,
+                input - accepted
+            );
+            state.flagged.insert(user_id);
+        }
+        Ok(())
+    }
+}
+
+/// Time is up, users with most taps win
+pub fn timeout(
+    state: &ButtonMashState,
+    sessions: &mut HashMap<usize, RoomSlot>,
+) -> Result<(), IuroError> {
+    let max = state.taps.values().copied().max().unwrap_or(0);
+
+    // Nobody wins if nobody tapped
+    if max == 0 {
+        return Ok(());
+    }
+
+    for (id, _) in state.taps.iter().filter(|(_, taps)| **taps == max) {
+        let slot = sessions
+            .get_mut(id)
+            // This should never happen
+            .ok_or(IuroError::AddrNotFound(*id))?;
+
+        slot.wins += 1;
+    }
+    Ok(())
+}
+
+/// Reveals everyone's taps and who was flagged
+pub fn results(state: &ButtonMashState, sessions: &HashMap<usize, RoomSlot>) -> MashResults {
+    let name = |id: &usize| sessions.get(id).map(|slot| slot.name.clone());
+    MashResults {
+        taps: state
+            .taps
+            .iter()
+            .filter_map(|(id, taps)| Some((name(id)?, *taps)))
+            .collect(),
+        flagged: state.flagged.iter().filter_map(name).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sessions;
+    use actix::System;
+
+    fn tap(state: &mut ButtonMashState, user_id: usize, input: u32) {
+        let update = Update {
+            user_id,
+            input,
+            state,
+        };
+        update.consume().expect("taps are always accepted");
+    }
+
+    #[test]
+    fn taps_above_cap_are_flagged() {
+        let mut state = ButtonMashState::new();
+        tap(&mut state, 0, BURST);
+        assert_eq!(state.taps[&0], BURST);
+        assert!(state.flagged.is_empty());
+
+        tap(&mut state, 0, 5);
+        assert_eq!(state.taps[&0], BURST);
+        assert!(state.flagged.contains(&0));
+    }
+
+    #[test]
+    fn cap_grows_with_time() {
+        let mut state = ButtonMashState::new();
+        // Pretends the game started a second ago
+        state.started -= Duration::from_secs(1);
+        tap(&mut state, 0, MAX_TAPS_PER_SECOND + BURST);
+        assert_eq!(state.taps[&0], MAX_TAPS_PER_SECOND + BURST);
+        assert!(state.flagged.is_empty());
+
+        // Taps after the window count as if they were sent at its end
+        state.started -= WINDOW;
+        tap(&mut state, 1, u32::MAX);
+        let cap = WINDOW.as_secs() as u32 * MAX_TAPS_PER_SECOND + BURST;
+        assert_eq!(state.taps[&1], cap);
+        assert!(state.flagged.contains(&1));
+    }
+
+    #[test]
+    fn most_taps_win() {
+        let _system = System::new("test");
+        let mut sessions = sessions(3);
+        let mut state = ButtonMashState::new();
+        timeout(&state, &mut sessions).expect("users are in the room");
+        assert!(sessions.values().all(|slot| slot.wins == 0));
+
+        tap(&mut state, 0, 4);
+        tap(&mut state, 1, 4);
+        tap(&mut state, 2, 3);
+        timeout(&state, &mut sessions).expect("users are in the room");
+        assert_eq!(sessions[&0].wins, 1);
+        assert_eq!(sessions[&1].wins, 1);
+        assert_eq!(sessions[&2].wins, 0);
+        assert_eq!(results(&state, &sessions).taps["user-2"], 3);
+    }
+}
@@ -0,0 +1,301 @@
+//! Users estimate a number, whoever gets closest wins, ties go to whoever guessed first.
+//!
+//! The number is either the answer to a question from the bundled dataset, or how many cells are
+//! filled in a grid the client generates from seeded parameters.
+
+use crate::prelude::*;
+use rand::{distributions::Standard, prelude::*};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::OnceLock, time::Duration};
+
+/// How long users have to guess
+pub const TIME_LIMIT: Duration = Duration::from_secs(30);
+
+const BUNDLED_ESTIMATES: &str = include_str!("../../assets/estimates.txt");
+
+/// What users must estimate
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub enum Estimate {
+    /// Question from the bundled dataset
+    Question(String),
+    /// How many cells of the grid are filled, see `grid_cells` for how it's generated
+    Grid {
+        seed: u32,
+        width: u8,
+        height: u8,
+        /// Percentage of cells that tend to be filled
+        density: u8,
+    },
+}
+
+impl Distribution<Estimate> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Estimate {
+        if rng.gen() {
+            // Bundled dataset is never empty
+            if let Some((question, _)) = questions().choose(rng) {
+                return Estimate::Question(question.clone());
+            }
+        }
+
+        Estimate::Grid {
+            // Seed can't be zero, otherwise xorshift only generates zeros
+            seed: rng.gen_range(1, u32::MAX),
+            width: rng.gen_range(8, 17),
+            height: rng.gen_range(6, 11),
+            density: rng.gen_range(20, 71),
+        }
+    }
+}
+
+impl Estimate {
+    /// Value users must estimate
+    pub fn answer(&self) -> i64 {
+        match self {
+            Estimate::Question(question) => questions()
+                .iter()
+                .find(|(known, _)| known == question)
+                .map_or(0, |(_, answer)| *answer),
+            Estimate::Grid {
+                seed,
+                width,
+                height,
+                density,
+            } => grid_cells(*seed, *width, *height, *density)
+                .filter(|filled| *filled)
+                .count() as i64,
+        }
+    }
+}
+
+/// Questions and their answers, parsed from `question|answer` lines
+fn questions() -> &'static [(String, i64)] {
+    static QUESTIONS: OnceLock<Vec<(String, i64)>> = OnceLock::new();
+
+    QUESTIONS.get_or_init(|| {
+        BUNDLED_ESTIMATES
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (question, answer) = line.split_once('|')?;
+                Some((question.trim().to_owned(), answer.trim().parse().ok()?))
+            })
+            .collect()
+    })
+}
+
+/// Generates if each cell of the grid is filled, row by row, using xorshift32
+///
+/// Clients must generate the grid the same way to show it
+pub fn grid_cells(seed: u32, width: u8, height: u8, density: u8) -> impl Iterator<Item = bool> {
+    let mut state = seed;
+    (0..usize::from(width) * usize::from(height)).map(move |_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state % 100 < u32::from(density)
+    })
+}
+
+/// Guess of a user and when it was submitted, relative to the others
+#[derive(Debug)]
+pub struct Guess {
+    pub value: i64,
+    pub order: usize,
+}
+
+/// Value to be estimated and users' guesses
+#[derive(Debug)]
+pub struct ClosestGuessState {
+    pub answer: i64,
+    pub guesses: HashMap<usize, Guess>,
+    /// How many guesses were submitted, to order them
+    pub submissions: usize,
+}
+
+/// User's guess and how far it was from the answer
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct GuessResult {
+    pub name: String,
+    pub guess: i64,
+    pub distance: u64,
+}
+
+/// True value and every guess, closest first, sent with the game results
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct GuessResults {
+    pub answer: i64,
+    pub guesses: Vec<GuessResult>,
+}
+
+pub struct Update<'a> {
+    pub user_id: usize,
+    pub input: i64,
+    pub state: &'a mut ClosestGuessState,
+}
+
+impl<'a> Update<'a> {
+    pub fn consume(self, sessions: &mut HashMap<usize, RoomSlot>) -> Result<bool, IuroError> {
+        let Update {
+            state,
+            user_id,
+            input,
+        } = self;
+
+        state.submissions += 1;
+        let guess = Guess {
+            value: input,
+            order: state.submissions,
+        };
+        state.guesses.insert(user_id, guess);
+
+        // All users guessed
+        if state.guesses.len() == sessions.len() {
+            award(state, sessions)?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+/// Guesses ordered by distance to the answer, and by who guessed first
+fn rank(state: &ClosestGuessState) -> Vec<(usize, &Guess)> {
+    let mut ranking: Vec<(usize, &Guess)> = state
+        .guesses
+        .iter()
+        .map(|(id, guess)| (*id, guess))
+        .collect();
+    ranking.sort_by_key(|(_, guess)| (guess.value.abs_diff(state.answer), guess.order));
+    ranking
+}
+
+/// Gives a win to the closest guess
+fn award(
+    state: &ClosestGuessState,
+    sessions: &mut HashMap<usize, RoomSlot>,
+) -> Result<(), IuroError> {
+    if let Some((id, _)) = rank(state).first() {
+        let slot = sessions
+            .get_mut(id)
+            // This should never happen
+            .ok_or(IuroError::AddrNotFound(*id))?;
+
+        slot.wins += 1;
+    }
+    Ok(())
+}
+
+/// Time is up, closest guess among the users that guessed wins
+pub fn timeout(
+    state: &ClosestGuessState,
+    sessions: &mut HashMap<usize, RoomSlot>,
+) -> Result<(), IuroError> {
+    award(state, sessions)
+}
+
+/// Reveals answer and how far each guess was from it
+pub fn results(state: &ClosestGuessState, sessions: &HashMap<usize, RoomSlot>) -> GuessResults {
+    let guesses = rank(state)
+        .into_iter()
+        .filter_map(|(id, guess)| {
+            sessions.get(&id).map(|slot| GuessResult {
+                name: slot.name.clone(),
+                guess: guess.value,
+                distance: guess.value.abs_diff(state.answer),
+            })
+        })
+        .collect();
+
+    GuessResults {
+        answer: state.answer,
+        guesses,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sessions;
+    use actix::System;
+
+    fn guess(
+        state: &mut ClosestGuessState,
+        sessions: &mut HashMap<usize, RoomSlot>,
+        user_id: usize,
+        input: i64,
+    ) -> bool {
+        let update = Update {
+            user_id,
+            input,
+            state,
+        };
+        update.consume(sessions).expect("user is in the room")
+    }
+
+    fn state(answer: i64) -> ClosestGuessState {
+        ClosestGuessState {
+            answer,
+            guesses: HashMap::default(),
+            submissions: 0,
+        }
+    }
+
+    #[test]
+    fn closest_guess_wins() {
+        let _system = System::new("test");
+        let mut sessions = sessions(3);
+        let mut state = state(100);
+        assert!(!guess(&mut state, &mut sessions, 0, 50));
+        assert!(!guess(&mut state, &mut sessions, 1, 140));
+        assert!(guess(&mut state, &mut sessions, 2, 120));
+        assert_eq!(sessions[&2].wins, 1);
+        assert_eq!(sessions[&0].wins + sessions[&1].wins, 0);
+
+        let results = results(&state, &sessions);
+        let distances: Vec<_> = results.guesses.iter().map(|g| g.distance).collect();
+        assert_eq!(distances, vec![20, 40, 50]);
+    }
+
+    #[test]
+    fn ties_go_to_earliest_guess() {
+        let _system = System::new("test");
+        let mut sessions = sessions(3);
+        let mut state = state(10);
+        guess(&mut state, &mut sessions, 2, 12);
+        guess(&mut state, &mut sessions, 0, 8);
+        // Changing a guess makes it the latest one
+        guess(&mut state, &mut sessions, 2, 12);
+        timeout(&state, &mut sessions).expect("users are in the room");
+        assert_eq!(sessions[&0].wins, 1);
+        assert_eq!(sessions[&2].wins, 0);
+    }
+
+    #[test]
+    fn grid_answer_counts_filled_cells() {
+        // First values of xorshift32 seeded with 1, modulo 100, are 69, 89, 61, 95, 33 and 4
+        let cells: Vec<bool> = grid_cells(1, 3, 2, 62).collect();
+        assert_eq!(cells, vec![false, false, true, false, true, true]);
+
+        let grid = Estimate::Grid {
+            seed: 1,
+            width: 3,
+            height: 2,
+            density: 62,
+        };
+        assert_eq!(grid.answer(), 3);
+        let empty = Estimate::Grid {
+            seed: 1,
+            width: 16,
+            height: 10,
+            density: 0,
+        };
+        assert_eq!(empty.answer(), 0);
+    }
+
+    #[test]
+    fn questions_have_answers() {
+        let (question, answer) = questions().first().expect("bundled questions");
+        assert_eq!(Estimate::Question(question.clone()).answer(), *answer);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::prelude::*;
+use rand::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// How many distinct values the sequence is made of
+pub const POOL_SIZE: usize = 4;
+/// Length of the sequence revealed in the first round
+pub const INITIAL_LENGTH: usize = 3;
+/// Every user still playing wins once the sequence gets this long
+pub const MAX_LENGTH: usize = 16;
+
+/// Sequence being repeated and which users are still playing
+#[derive(Debug)]
+pub struct IuroSaysState {
+    pub pool: Vec<u8>,
+    pub sequence: Vec<u8>,
+    /// Users that weren't eliminated yet
+    pub players: HashSet<usize>,
+    /// Inputs of the current round
+    pub answers: HashMap<usize, Vec<u8>>,
+}
+
+/// Possible results of a user repeating the sequence
+pub enum Outcome {
+    /// Some users still have to answer
+    Waiting,
+    /// Round ended with more than one survivor, sequence grew
+    NextRound,
+    /// Game ended, winners already got their wins
+    Ended,
+}
+
+pub struct Update<'a, R: Rng> {
+    pub user_id: usize,
+    pub input: Vec<u8>,
+    pub state: &'a mut IuroSaysState,
+    pub rng: &'a mut R,
+}
+
+impl<'a, R: Rng> Update<'a, R> {
+    pub fn consume(self, sessions: &mut HashMap<usize, RoomSlot>) -> Result<Outcome, IuroError> {
+        let Update {
+            state,
+            user_id,
+            input,
+            rng,
+        } = self;
+
+        if !state.players.contains(&user_id) {
+            return Err(IuroError::Eliminated);
+        }
+        state.answers.insert(user_id, input);
+
+        // Users still playing didn't answer yet
+        if state.answers.len() < state.players.len() {
+            return Ok(Outcome::Waiting);
+        }
+
+        let sequence = &state.sequence;
+        // Answers of the last round are kept to be revealed when the game ends
+        let survivors: HashSet<usize> = state
+            .answers
+            .iter()
+            .filter(|(_, answer)| *answer == sequence)
+            .map(|(id, _)| *id)
+            .collect();
+
+        // If everybody made a mistake in the same round they all win
+        let winners = match survivors.len() {
+            0 => Some(&state.players),
+            1 => Some(&survivors),
+            _ if state.sequence.len() >= MAX_LENGTH => Some(&survivors),
+            _ => None,
+        };
+
+        if let Some(winners) = winners {
+            for id in winners {
+                let slot = sessions
+                    .get_mut(id)
+                    // This should never happen
+                    .ok_or(IuroError::AddrNotFound(*id))?;
+
+                slot.wins += 1;
+            }
+            return Ok(Outcome::Ended);
+        }
+
+        state.players = survivors;
+        state.answers.clear();
+        // Pool is never empty
+        state.sequence.push(*state.pool.choose(rng).unwrap_or(&0));
+        Ok(Outcome::NextRound)
+    }
+}
+
+/// Sequence to be repeated and names of users still playing
+pub fn reveal(
+    state: &IuroSaysState,
+    sessions: &HashMap<usize, RoomSlot>,
+) -> (Vec<u8>, Vec<String>) {
+    let players = state
+        .players
+        .iter()
+        .filter_map(|id| sessions.get(id))
+        .map(|slot| slot.name.clone())
+        .collect();
+    (state.sequence.clone(), players)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sessions;
+    use actix::System;
+
+    fn state(users: usize) -> IuroSaysState {
+        IuroSaysState {
+            pool: vec![1, 2, 3, 4],
+            sequence: vec![1, 2, 3],
+            players: (0..users).collect(),
+            answers: HashMap::default(),
+        }
+    }
+
+    fn repeat(
+        state: &mut IuroSaysState,
+        sessions: &mut HashMap<usize, RoomSlot>,
+        user_id: usize,
+        input: &[u8],
+    ) -> Result<Outcome, IuroError> {
+        let update = Update {
+            user_id,
+            input: input.to_vec(),
+            state,
+            rng: &mut StdRng::seed_from_u64(0),
+        };
+        update.consume(sessions)
+    }
+
+    #[test]
+    fn wrong_repeat_eliminates_user() {
+        let _system = System::new("test");
+        let mut sessions = sessions(3);
+        let mut state = state(3);
+
+        let outcome = repeat(&mut state, &mut sessions, 0, &[1, 2, 3]);
+        assert!(matches!(outcome, Ok(Outcome::Waiting)));
+        let outcome = repeat(&mut state, &mut sessions, 1, &[1, 3, 2]);
+        assert!(matches!(outcome, Ok(Outcome::Waiting)));
+        let outcome = repeat(&mut state, &mut sessions, 2, &[1, 2, 3]);
+        assert!(matches!(outcome, Ok(Outcome::NextRound)));
+
+        assert_eq!(state.players, [0, 2].iter().copied().collect());
+        assert_eq!(state.sequence.len(), 4);
+        assert!(state.pool.contains(&state.sequence[3]));
+        assert!(matches!(
+            repeat(&mut state, &mut sessions, 1, &[1, 2, 3]),
+            Err(IuroError::Eliminated)
+        ));
+
+        // Last survivor wins
+        let sequence = state.sequence.clone();
+        repeat(&mut state, &mut sessions, 0, &sequence).expect("user is playing");
+        let outcome = repeat(&mut state, &mut sessions, 2, &[4]);
+        assert!(matches!(outcome, Ok(Outcome::Ended)));
+        assert_eq!(sessions[&0].wins, 1);
+        assert_eq!(sessions[&1].wins + sessions[&2].wins, 0);
+    }
+
+    #[test]
+    fn everybody_wrong_wins_together() {
+        let _system = System::new("test");
+        let mut sessions = sessions(2);
+        let mut state = state(2);
+        repeat(&mut state, &mut sessions, 0, &[3]).expect("user is playing");
+        let outcome = repeat(&mut state, &mut sessions, 1, &[2]);
+        assert!(matches!(outcome, Ok(Outcome::Ended)));
+        assert!(sessions.values().all(|slot| slot.wins == 1));
+    }
+}
@@ -0,0 +1,227 @@
+//! Users vote in one of the prompt's options, either the majority or the minority scores,
+//! which one is only revealed with the results.
+
+use crate::prelude::*;
+use rand::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::OnceLock};
+
+const BUNDLED_PROMPTS: &str = include_str!("../../assets/prompts.txt");
+
+/// Question and the options users may vote for
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Prompt {
+    pub question: String,
+    pub options: Vec<String>,
+}
+
+/// Parses one prompt per line, as `question|option|option[|option]`, ignoring `#` comments
+fn parse(text: &str) -> Vec<Prompt> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split('|').map(|part| part.trim().to_owned());
+            let question = parts.next()?;
+            let options: Vec<String> = parts.collect();
+            if (2..=3).contains(&options.len()) {
+                Some(Prompt { question, options })
+            } else {
+                warn!("Ignoring invalid prompt: {}", line);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Picks one of the bundled prompts
+pub fn choose<R: Rng + ?Sized>(rng: &mut R) -> Prompt {
+    static PROMPTS: OnceLock<Vec<Prompt>> = OnceLock::new();
+
+    PROMPTS
+        .get_or_init(|| parse(BUNDLED_PROMPTS))
+        .choose(rng)
+        .cloned()
+        // Bundled prompts are never empty
+        .unwrap_or_else(|| Prompt {
+            question: "Yes or no?".to_owned(),
+            options: vec!["Yes".to_owned(), "No".to_owned()],
+        })
+}
+
+/// Votes of each user, and if the minority scores this round
+#[derive(Debug)]
+pub struct MajorityState {
+    pub options: usize,
+    pub minority_wins: bool,
+    pub votes: HashMap<usize, usize>,
+}
+
+/// How users voted, sent with the game results
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct VoteSplit {
+    /// If the minority scored, otherwise the majority did
+    pub minority_wins: bool,
+    /// How many votes each option got
+    pub votes: Vec<usize>,
+}
+
+pub struct Update<'a> {
+    pub user_id: usize,
+    pub input: usize,
+    pub state: &'a mut MajorityState,
+}
+
+impl<'a> Update<'a> {
+    pub fn consume(
+        self,
+        sessions: &mut HashMap<usize, RoomSlot>,
+    ) -> Result<Option<VoteSplit>, IuroError> {
+        let Update {
+            state,
+            user_id,
+            input,
+        } = self;
+
+        if input >= state.options {
+            return Err(IuroError::InvalidInput);
+        }
+        state.votes.insert(user_id, input);
+
+        // Not all users voted
+        if state.votes.len() < sessions.len() {
+            return Ok(None);
+        }
+
+        let mut votes = vec![0; state.options];
+        for option in state.votes.values() {
+            votes[*option] += 1;
+        }
+
+        // Options nobody voted for are neither majority nor minority
+        let counts = votes.iter().copied().filter(|count| *count > 0);
+        let threshold = if state.minority_wins {
+            counts.min()
+        } else {
+            counts.max()
+        };
+
+        // If everybody voted the same there is no minority
+        let unanimous = votes.iter().filter(|count| **count > 0).count() == 1;
+        if let Some(threshold) = threshold.filter(|_| !(state.minority_wins && unanimous)) {
+            for (id, _) in state
+                .votes
+                .iter()
+                .filter(|(_, option)| votes[**option] == threshold)
+            {
+                let slot = sessions
+                    .get_mut(id)
+                    // This should never happen
+                    .ok_or(IuroError::AddrNotFound(*id))?;
+
+                slot.wins += 1;
+            }
+        }
+
+        Ok(Some(VoteSplit {
+            minority_wins: state.minority_wins,
+            votes,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sessions;
+    use actix::System;
+
+    /// Every user votes, in order, returns the split once everybody voted
+    fn vote(minority_wins: bool, votes: &[usize]) -> (VoteSplit, Vec<usize>) {
+        let mut sessions = sessions(votes.len());
+        let mut state = MajorityState {
+            options: 3,
+            minority_wins,
+            votes: HashMap::default(),
+        };
+        let mut split = None;
+        for (user_id, input) in votes.iter().enumerate() {
+            let update = Update {
+                user_id,
+                input: *input,
+                state: &mut state,
+            };
+            split = update.consume(&mut sessions).expect("valid vote");
+        }
+        let wins = (0..votes.len()).map(|id| sessions[&id].wins).collect();
+        (split.expect("everybody voted"), wins)
+    }
+
+    #[test]
+    fn majority_or_minority_scores() {
+        let _system = System::new("test");
+        let (split, wins) = vote(false, &[0, 0, 1, 2]);
+        assert_eq!(split.votes, vec![2, 1, 1]);
+        assert!(!split.minority_wins);
+        assert_eq!(wins, vec![1, 1, 0, 0]);
+
+        let (split, wins) = vote(true, &[0, 0, 0, 1]);
+        assert!(split.minority_wins);
+        assert_eq!(wins, vec![0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn tied_options_all_score() {
+        let _system = System::new("test");
+        let (split, wins) = vote(false, &[0, 0, 1, 1]);
+        assert_eq!(split.votes, vec![2, 2, 0]);
+        assert_eq!(wins, vec![1, 1, 1, 1]);
+
+        // Option nobody voted for isn't the minority
+        let (_, wins) = vote(true, &[0, 1, 1, 2, 2]);
+        assert_eq!(wins, vec![1, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn unanimous_vote_has_no_minority() {
+        let _system = System::new("test");
+        let (split, wins) = vote(true, &[2, 2, 2]);
+        assert_eq!(split.votes, vec![0, 0, 3]);
+        assert_eq!(wins, vec![0, 0, 0]);
+
+        let (_, wins) = vote(false, &[2, 2, 2]);
+        assert_eq!(wins, vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn rejects_missing_options() {
+        let _system = System::new("test");
+        let mut sessions = sessions(1);
+        let mut state = MajorityState {
+            options: 2,
+            minority_wins: false,
+            votes: HashMap::default(),
+        };
+        let update = Update {
+            user_id: 0,
+            input: 2,
+            state: &mut state,
+        };
+        assert!(matches!(
+            update.consume(&mut sessions),
+            Err(IuroError::InvalidInput)
+        ));
+        assert!(state.votes.is_empty());
+    }
+
+    #[test]
+    fn parses_prompts() {
+        let prompts =
+            parse("# comment\n\nCats or dogs?|Cats|Dogs\nToo few|One\n A? | B | C | D \n");
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[0].question, "Cats or dogs?");
+        assert_eq!(prompts[1].options, vec!["B", "C", "D"]);
+        assert!(!parse(BUNDLED_PROMPTS).is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::prelude::*;
+use rand::{distributions::Standard, prelude::*};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+/// How many problems each sprint has
+pub const PROBLEMS: usize = 5;
+/// How long users have to solve the problems before the one with most correct answers wins
+pub const TIME_LIMIT: Duration = Duration::from_secs(60);
+
+/// Arithmetic operations a problem can have
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug)]
+pub enum MathOperator {
+    Add,
+    Subtract,
+    Multiply,
+}
+
+/// Arithmetic problem, the answer is never sent to the user
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug)]
+pub struct MathProblem {
+    pub lhs: i32,
+    pub operator: MathOperator,
+    pub rhs: i32,
+}
+
+impl MathProblem {
+    /// Expected answer to the problem
+    pub fn answer(self) -> i32 {
+        match self.operator {
+            MathOperator::Add => self.lhs + self.rhs,
+            MathOperator::Subtract => self.lhs - self.rhs,
+            MathOperator::Multiply => self.lhs * self.rhs,
+        }
+    }
+}
+
+impl Distribution<MathProblem> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> MathProblem {
+        let (operator, max) = match rng.gen_range(0, 3) {
+            0 => (MathOperator::Add, 100),
+            1 => (MathOperator::Subtract, 100),
+            _ => (MathOperator::Multiply, 13),
+        };
+
+        MathProblem {
+            lhs: rng.gen_range(0, max),
+            operator,
+            rhs: rng.gen_range(0, max),
+        }
+    }
+}
+
+/// Problems to be solved and how each user is doing
+#[derive(Debug)]
+pub struct MathSprintState {
+    pub problems: Vec<MathProblem>,
+    /// How many problems each user solved
+    pub solved: HashMap<usize, usize>,
+    /// Each user's last answer, right or wrong
+    pub answers: HashMap<usize, i32>,
+}
+
+impl MathSprintState {
+    pub fn new(problems: Vec<MathProblem>) -> Self {
+        Self {
+            problems,
+            solved: HashMap::default(),
+            answers: HashMap::default(),
+        }
+    }
+}
+
+pub struct Update<'a> {
+    pub user_id: usize,
+    pub input: i32,
+    pub state: &'a mut MathSprintState,
+}
+
+impl<'a> Update<'a> {
+    pub fn consume(self, sessions: &mut HashMap<usize, RoomSlot>) -> Result<bool, IuroError> {
+        let Update {
+            state,
+            user_id,
+            input,
+        } = self;
+        state.answers.insert(user_id, input);
+
+        // Wrong answers don't advance, user must try again
+        let solved = state.solved.entry(user_id).or_insert(0);
+        if state.problems[*solved].answer() == input {
+            *solved += 1;
+        }
+
+        // First user to solve all problems wins
+        if *solved == state.problems.len() {
+            let slot = sessions
+                .get_mut(&user_id)
+                // This should never happen
+                .ok_or(IuroError::AddrNotFound(user_id))?;
+
+            slot.wins += 1;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+/// Time is up, users with the most correct answers win
+pub fn timeout(
+    state: &MathSprintState,
+    sessions: &mut HashMap<usize, RoomSlot>,
+) -> Result<(), IuroError> {
+    let max = state.solved.values().copied().max().unwrap_or(0);
+
+    // Nobody wins if no problem was solved
+    if max == 0 {
+        return Ok(());
+    }
+
+    for (id, _) in state.solved.iter().filter(|(_, solved)| **solved == max) {
+        let slot = sessions
+            .get_mut(id)
+            // This should never happen
+            .ok_or(IuroError::AddrNotFound(*id))?;
+
+        slot.wins += 1;
+    }
+    Ok(())
+}
+
+/// How many problems each user has solved
+pub fn progress(
+    state: &MathSprintState,
+    sessions: &HashMap<usize, RoomSlot>,
+) -> HashMap<String, usize> {
+    sessions
+        .iter()
+        .map(|(id, slot)| (slot.name.clone(), *state.solved.get(id).unwrap_or(&0)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sessions;
+    use actix::System;
+
+    fn answer(
+        state: &mut MathSprintState,
+        sessions: &mut HashMap<usize, RoomSlot>,
+        user_id: usize,
+        input: i32,
+    ) -> bool {
+        let update = Update {
+            user_id,
+            input,
+            state,
+        };
+        update.consume(sessions).expect("user is in the room")
+    }
+
+    #[test]
+    fn first_to_solve_every_problem_wins() {
+        let _system = System::new("test");
+        let mut sessions = sessions(2);
+        let problem = |lhs, operator, rhs| MathProblem { lhs, operator, rhs };
+        let mut state = MathSprintState::new(vec![
+            problem(2, MathOperator::Add, 3),
+            problem(7, MathOperator::Multiply, 6),
+        ]);
+
+        assert!(!answer(&mut state, &mut sessions, 0, 5));
+        assert!(!answer(&mut state, &mut sessions, 1, 5));
+        // Wrong answers don't advance
+        assert!(!answer(&mut state, &mut sessions, 0, 41));
+        assert_eq!(state.solved[&0], 1);
+        assert_eq!(state.answers[&0], 41);
+
+        assert!(answer(&mut state, &mut sessions, 1, 42));
+        assert_eq!(sessions[&1].wins, 1);
+        assert_eq!(sessions[&0].wins, 0);
+    }
+
+    #[test]
+    fn most_solved_win_when_time_is_up() {
+        let _system = System::new("test");
+        let mut sessions = sessions(3);
+        let mut state = MathSprintState::new(Vec::new());
+        timeout(&state, &mut sessions).expect("users are in the room");
+        assert!(sessions.values().all(|slot| slot.wins == 0));
+
+        state.solved = vec![(0, 2), (1, 2), (2, 1)].into_iter().collect();
+        timeout(&state, &mut sessions).expect("users are in the room");
+        assert_eq!(sessions[&0].wins, 1);
+        assert_eq!(sessions[&1].wins, 1);
+        assert_eq!(sessions[&2].wins, 0);
+    }
+}
@@ -1,37 +1,286 @@
 use actix::prelude::*;
-use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, collections::HashMap, time::Duration};
 
+pub mod board;
+pub mod button_mash;
+pub mod closest_guess;
+pub mod iuro_says;
+pub mod majority;
+pub mod math_sprint;
+pub mod prisoners_dilemma;
+pub mod reveal;
 pub mod rock_papiuro_scissor;
 pub mod the_right_iuro;
+pub mod typing_race;
+pub mod word_scramble;
 
 pub mod prelude {
-    pub use super::{GameInput, GameState, Game};
+    pub use super::board::{BoardKind, BoardState, BoardView};
+    pub use super::button_mash::{ButtonMashState, MashResults};
+    pub use super::closest_guess::{grid_cells, ClosestGuessState, Estimate, GuessResults};
+    pub use super::iuro_says::IuroSaysState;
+    pub use super::majority::{MajorityState, Prompt, VoteSplit};
+    pub use super::math_sprint::{MathProblem, MathSprintState};
+    pub use super::prisoners_dilemma::{DilemmaChoice, DilemmaState};
+    pub use super::reveal::Reveal;
     pub use super::rock_papiuro_scissor::RockPapiuroScissorInput;
+    pub use super::typing_race::{TypingRaceState, TypingResult};
+    pub use super::word_scramble::WordScrambleState;
+    pub use super::{Game, GameInput, GameKind, GameProgress, GameResults, GameState};
 }
 
 use crate::prelude::*;
 
 /// Game input sent from user
-#[derive(Deserialize, Message, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Message, Debug)]
 pub enum GameInput {
     RockPapiuroScissor(RockPapiuroScissorInput),
     TheRightIuro(Vec<u8>),
+    /// Answer to the user's current problem
+    MathSprint(i32),
+    /// User's attempt at repeating the whole sequence
+    IuroSays(Vec<u8>),
+    /// User's guess of the scrambled word
+    WordScramble(String),
+    /// Index of the option user voted for
+    Majority(usize),
+    /// User's choice against its opponent in the current iteration
+    PrisonersDilemma(DilemmaChoice),
+    /// Characters typed since user's last update
+    TypingRace(String),
+    /// User's estimate
+    ClosestGuess(i64),
+    /// Cell to place the piece in, only when it's user's turn
+    TicTacToe(usize),
+    /// Column to drop the piece in, only when it's user's turn
+    ConnectFour(usize),
+    /// How many taps since last input, sessions batch them before sending to the server
+    ButtonMash(u32),
 }
 
 /// Enumerates available games
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub enum Game {
     /// Rock Paper Scissors Iuro's version
     RockPapiuroScissor,
-    TheRightIuro(Vec<u8>)
+    TheRightIuro(Vec<u8>),
+    /// Problems to be solved in order
+    MathSprint(Vec<MathProblem>),
+    /// Pool of values and the first sequence to be repeated, the sequence grows every round
+    IuroSays((Vec<u8>, Vec<u8>)),
+    /// Scrambled letters of the word to be guessed, holds the word itself while queued
+    WordScramble(String),
+    /// Question and options to be voted for
+    Majority(Prompt),
+    /// Pairs of users playing against each other, filled when the game starts
+    PrisonersDilemma(Vec<(String, String)>),
+    /// Passage to be typed
+    TypingRace(String),
+    /// What users must estimate
+    ClosestGuess(Estimate),
+    /// Users of each side of each board, in the order they play, filled when the game starts
+    TicTacToe(Vec<[Vec<String>; 2]>),
+    /// Users of each side of each board, in the order they play, filled when the game starts
+    ConnectFour(Vec<[Vec<String>; 2]>),
+    /// Milliseconds users have to tap
+    ButtonMash(u64),
+}
+
+impl Game {
+    pub fn kind(&self) -> GameKind {
+        match self {
+            Game::RockPapiuroScissor => GameKind::RockPapiuroScissor,
+            Game::TheRightIuro(_) => GameKind::TheRightIuro,
+            Game::MathSprint(_) => GameKind::MathSprint,
+            Game::IuroSays(_) => GameKind::IuroSays,
+            Game::WordScramble(_) => GameKind::WordScramble,
+            Game::Majority(_) => GameKind::Majority,
+            Game::PrisonersDilemma(_) => GameKind::PrisonersDilemma,
+            Game::TypingRace(_) => GameKind::TypingRace,
+            Game::ClosestGuess(_) => GameKind::ClosestGuess,
+            Game::TicTacToe(_) => GameKind::TicTacToe,
+            Game::ConnectFour(_) => GameKind::ConnectFour,
+            Game::ButtonMash(_) => GameKind::ButtonMash,
+        }
+    }
+
+    /// How long the game may run before it's forcefully ended, `None` if it waits for every user
+    pub fn time_limit(&self) -> Option<Duration> {
+        match self {
+            Game::RockPapiuroScissor
+            | Game::TheRightIuro(_)
+            | Game::IuroSays(_)
+            | Game::Majority(_)
+            | Game::PrisonersDilemma(_)
+            | Game::TicTacToe(_)
+            | Game::ConnectFour(_) => None,
+            Game::MathSprint(_) => Some(math_sprint::TIME_LIMIT),
+            Game::WordScramble(_) => Some(word_scramble::TIME_LIMIT),
+            Game::TypingRace(_) => Some(typing_race::TIME_LIMIT),
+            Game::ClosestGuess(_) => Some(closest_guess::TIME_LIMIT),
+            Game::ButtonMash(_) => Some(button_mash::WINDOW),
+        }
+    }
 }
 
+/// Identifies each game, without its data
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GameKind {
+    RockPapiuroScissor,
+    TheRightIuro,
+    MathSprint,
+    IuroSays,
+    WordScramble,
+    Majority,
+    PrisonersDilemma,
+    TypingRace,
+    ClosestGuess,
+    TicTacToe,
+    ConnectFour,
+    ButtonMash,
+}
+
+impl GameKind {
+    /// Every available game
+    pub const ALL: [GameKind; 12] = [
+        GameKind::RockPapiuroScissor,
+        GameKind::TheRightIuro,
+        GameKind::MathSprint,
+        GameKind::IuroSays,
+        GameKind::WordScramble,
+        GameKind::Majority,
+        GameKind::PrisonersDilemma,
+        GameKind::TypingRace,
+        GameKind::ClosestGuess,
+        GameKind::TicTacToe,
+        GameKind::ConnectFour,
+        GameKind::ButtonMash,
+    ];
+}
 
 /// Each game holds its own state
 #[derive(Debug)]
 pub enum GameState {
     RockPapiuroScissor(HashMap<usize, RockPapiuroScissorInput>),
     TheRightIuro((Vec<u8>, HashMap<usize, Vec<u8>>)),
+    MathSprint(MathSprintState),
+    IuroSays(IuroSaysState),
+    WordScramble(WordScrambleState),
+    Majority(MajorityState),
+    PrisonersDilemma(DilemmaState),
+    TypingRace(TypingRaceState),
+    ClosestGuess(ClosestGuessState),
+    TicTacToe(BoardState),
+    ConnectFour(BoardState),
+    ButtonMash(ButtonMashState),
 }
 
+impl GameState {
+    /// Game's name, sent to users with its results
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameState::RockPapiuroScissor(_) => "RockPapiuroScissor",
+            GameState::TheRightIuro(_) => "TheRightIuro",
+            GameState::MathSprint(_) => "MathSprint",
+            GameState::IuroSays(_) => "IuroSays",
+            GameState::WordScramble(_) => "WordScramble",
+            GameState::Majority(_) => "Majority",
+            GameState::PrisonersDilemma(_) => "PrisonersDilemma",
+            GameState::TypingRace(_) => "TypingRace",
+            GameState::ClosestGuess(_) => "ClosestGuess",
+            GameState::TicTacToe(_) => "TicTacToe",
+            GameState::ConnectFour(_) => "ConnectFour",
+            GameState::ButtonMash(_) => "ButtonMash",
+        }
+    }
+
+    /// If users may replace their answer until the round resolves, otherwise the first one is final
+    pub fn answers_can_change(&self) -> bool {
+        match self {
+            // Users may change their mind until everybody voted or guessed
+            GameState::Majority(_) | GameState::ClosestGuess(_) => true,
+            GameState::RockPapiuroScissor(_)
+            | GameState::TheRightIuro(_)
+            | GameState::IuroSays(_)
+            | GameState::PrisonersDilemma(_) => false,
+            // Inputs aren't answers to a round, they are all consumed as they come
+            GameState::MathSprint(_)
+            | GameState::WordScramble(_)
+            | GameState::TypingRace(_)
+            | GameState::TicTacToe(_)
+            | GameState::ConnectFour(_)
+            | GameState::ButtonMash(_) => true,
+        }
+    }
+
+    /// Users that already answered the current round, `None` if game has no rounds to answer
+    pub fn locked_in(&self) -> Option<Vec<usize>> {
+        match self {
+            GameState::RockPapiuroScissor(state) => Some(state.keys().copied().collect()),
+            GameState::TheRightIuro((_, answers)) => Some(answers.keys().copied().collect()),
+            GameState::IuroSays(state) => Some(state.answers.keys().copied().collect()),
+            GameState::Majority(state) => Some(state.votes.keys().copied().collect()),
+            GameState::PrisonersDilemma(state) => Some(
+                state
+                    .matches
+                    .iter()
+                    .flat_map(|m| m.choices.keys().copied())
+                    .collect(),
+            ),
+            GameState::ClosestGuess(state) => Some(state.guesses.keys().copied().collect()),
+            GameState::MathSprint(_)
+            | GameState::WordScramble(_)
+            | GameState::TypingRace(_)
+            | GameState::TicTacToe(_)
+            | GameState::ConnectFour(_)
+            | GameState::ButtonMash(_) => None,
+        }
+    }
+
+    /// Moves game's clocks forward by `paused`, so time spent paused doesn't count
+    pub fn shift_clock(&mut self, paused: Duration) {
+        match self {
+            GameState::TypingRace(state) => state.started += paused,
+            GameState::ButtonMash(state) => state.started += paused,
+            GameState::RockPapiuroScissor(_)
+            | GameState::TheRightIuro(_)
+            | GameState::MathSprint(_)
+            | GameState::IuroSays(_)
+            | GameState::WordScramble(_)
+            | GameState::Majority(_)
+            | GameState::PrisonersDilemma(_)
+            | GameState::ClosestGuess(_)
+            | GameState::TicTacToe(_)
+            | GameState::ConnectFour(_) => {}
+        }
+    }
+}
+
+/// Running game's progress, sent after every accepted input
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct GameProgress {
+    pub game: Cow<'static, str>,
+    /// Game specific progress of each user, like problems solved or points earned
+    pub progress: HashMap<String, usize>,
+    /// Users that already answered the current round, never what they answered
+    pub locked_in: Vec<String>,
+}
+
+/// Game specific results, sent along with the wins when a game ends
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub enum GameResults {
+    /// How users voted
+    Majority(VoteSplit),
+    /// Final ranking of the race
+    TypingRace(Vec<TypingResult>),
+    /// True value and how far each guess was from it
+    ClosestGuess(GuessResults),
+    /// Final state of every board
+    TicTacToe(Vec<BoardView>),
+    /// Final state of every board
+    ConnectFour(Vec<BoardView>),
+    /// Taps of each user and who was flagged for cheating
+    ButtonMash(MashResults),
+}
@@ -0,0 +1,276 @@
+//! Users are paired and play several iterations of the prisoner's dilemma against their opponent.
+//!
+//! How many iterations each match has is never revealed, so nobody knows when the last one is.
+
+use crate::prelude::*;
+use actix::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Fewest iterations a match may have
+pub const MIN_ITERATIONS: usize = 3;
+/// Most iterations a match may have
+pub const MAX_ITERATIONS: usize = 6;
+
+/// Input options for `PrisonersDilemma`
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Message, Debug)]
+pub enum DilemmaChoice {
+    Cooperate,
+    Defect,
+}
+
+impl DilemmaChoice {
+    /// Points earned by user, given its opponent's choice
+    pub fn payoff(self, other: Self) -> usize {
+        match (self, other) {
+            (Self::Cooperate, Self::Cooperate) => 3,
+            (Self::Cooperate, Self::Defect) => 0,
+            (Self::Defect, Self::Cooperate) => 5,
+            (Self::Defect, Self::Defect) => 1,
+        }
+    }
+}
+
+/// Pair of users playing against each other
+#[derive(Debug)]
+pub struct DilemmaMatch {
+    pub players: (usize, usize),
+    /// Iterations already played
+    pub iteration: usize,
+    /// Choices of the current iteration
+    pub choices: HashMap<usize, DilemmaChoice>,
+    /// Choices of both players in every iteration already played
+    pub history: Vec<(DilemmaChoice, DilemmaChoice)>,
+}
+
+/// Matches being played and points each user earned
+#[derive(Debug)]
+pub struct DilemmaState {
+    pub matches: Vec<DilemmaMatch>,
+    pub iterations: usize,
+    pub points: HashMap<usize, usize>,
+}
+
+impl DilemmaState {
+    pub fn new(pairs: Vec<(usize, usize)>, iterations: usize) -> Self {
+        let points = pairs
+            .iter()
+            .flat_map(|&(a, b)| vec![(a, 0), (b, 0)])
+            .collect();
+        let matches = pairs
+            .into_iter()
+            .map(|players| DilemmaMatch {
+                players,
+                iteration: 0,
+                choices: HashMap::default(),
+                history: Vec::new(),
+            })
+            .collect();
+
+        Self {
+            matches,
+            iterations,
+            points,
+        }
+    }
+}
+
+/// Pairs users with the round robin circle method, each `rotation` gives different opponents
+///
+/// If there is an odd number of users one of them sits out
+pub fn pair(mut ids: Vec<usize>, rotation: usize) -> Vec<(usize, usize)> {
+    ids.sort_unstable();
+    let mut seats: Vec<Option<usize>> = ids.into_iter().map(Some).collect();
+    if seats.len() % 2 == 1 {
+        seats.push(None);
+    }
+
+    // First seat is fixed, the others rotate
+    if seats.len() > 2 {
+        let rotation = rotation % (seats.len() - 1);
+        seats[1..].rotate_right(rotation);
+    }
+
+    let half = seats.len() / 2;
+    (0..half)
+        .filter_map(|i| match (seats[i], seats[seats.len() - 1 - i]) {
+            (Some(a), Some(b)) => Some((a, b)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Possible results of a user's choice
+pub enum Outcome {
+    /// Opponent didn't choose yet
+    Waiting,
+    /// User's match finished an iteration
+    Progress,
+    /// Every match finished, winners already got their wins
+    Ended,
+}
+
+pub struct Update<'a> {
+    pub user_id: usize,
+    pub input: DilemmaChoice,
+    pub state: &'a mut DilemmaState,
+}
+
+impl<'a> Update<'a> {
+    pub fn consume(self, sessions: &mut HashMap<usize, RoomSlot>) -> Result<Outcome, IuroError> {
+        let Update {
+            state,
+            user_id,
+            input,
+        } = self;
+        let iterations = state.iterations;
+
+        let current = state
+            .matches
+            .iter_mut()
+            .find(|m| m.players.0 == user_id || m.players.1 == user_id)
+            // User is sitting out or match already ended
+            .filter(|m| m.iteration < iterations)
+            .ok_or(IuroError::InvalidInput)?;
+        current.choices.insert(user_id, input);
+
+        let (a, b) = current.players;
+        let (choice_a, choice_b) = match (current.choices.get(&a), current.choices.get(&b)) {
+            (Some(choice_a), Some(choice_b)) => (*choice_a, *choice_b),
+            _ => return Ok(Outcome::Waiting),
+        };
+        current.choices.clear();
+        current.history.push((choice_a, choice_b));
+        current.iteration += 1;
+
+        *state.points.entry(a).or_insert(0) += choice_a.payoff(choice_b);
+        *state.points.entry(b).or_insert(0) += choice_b.payoff(choice_a);
+
+        // Some matches are still being played
+        if state.matches.iter().any(|m| m.iteration < iterations) {
+            return Ok(Outcome::Progress);
+        }
+
+        let max = state.points.values().copied().max().unwrap_or(0);
+        for (id, _) in state.points.iter().filter(|(_, points)| **points == max) {
+            let slot = sessions
+                .get_mut(id)
+                // This should never happen
+                .ok_or(IuroError::AddrNotFound(*id))?;
+
+            slot.wins += 1;
+        }
+        Ok(Outcome::Ended)
+    }
+}
+
+/// Points each user earned so far
+pub fn progress(
+    state: &DilemmaState,
+    sessions: &HashMap<usize, RoomSlot>,
+) -> HashMap<String, usize> {
+    state
+        .points
+        .iter()
+        .filter_map(|(id, points)| sessions.get(id).map(|slot| (slot.name.clone(), *points)))
+        .collect()
+}
+
+/// Names of each pair of users
+pub fn pairs(state: &DilemmaState, sessions: &HashMap<usize, RoomSlot>) -> Vec<(String, String)> {
+    let name = |id| {
+        sessions
+            .get(&id)
+            .map_or_else(String::new, |slot| slot.name.clone())
+    };
+    state
+        .matches
+        .iter()
+        .map(|m| (name(m.players.0), name(m.players.1)))
+        .collect()
+}
+
+/// Every choice user made, in order
+pub fn choices(state: &DilemmaState, user_id: usize) -> Vec<DilemmaChoice> {
+    state
+        .matches
+        .iter()
+        .find_map(|m| match m.players {
+            (a, _) if a == user_id => Some(m.history.iter().map(|(a, _)| *a).collect()),
+            (_, b) if b == user_id => Some(m.history.iter().map(|(_, b)| *b).collect()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sessions;
+    use actix::System;
+    use DilemmaChoice::{Cooperate, Defect};
+
+    fn choose(
+        state: &mut DilemmaState,
+        sessions: &mut HashMap<usize, RoomSlot>,
+        user_id: usize,
+        input: DilemmaChoice,
+    ) -> Result<Outcome, IuroError> {
+        let update = Update {
+            user_id,
+            input,
+            state,
+        };
+        update.consume(sessions)
+    }
+
+    #[test]
+    fn payoff_matrix() {
+        assert_eq!(Cooperate.payoff(Cooperate), 3);
+        assert_eq!(Cooperate.payoff(Defect), 0);
+        assert_eq!(Defect.payoff(Cooperate), 5);
+        assert_eq!(Defect.payoff(Defect), 1);
+    }
+
+    #[test]
+    fn most_points_win_once_every_match_ends() {
+        let _system = System::new("test");
+        let mut sessions = sessions(4);
+        let mut state = DilemmaState::new(vec![(0, 1), (2, 3)], 2);
+
+        let outcome = choose(&mut state, &mut sessions, 0, Defect);
+        assert!(matches!(outcome, Ok(Outcome::Waiting)));
+        let outcome = choose(&mut state, &mut sessions, 1, Cooperate);
+        assert!(matches!(outcome, Ok(Outcome::Progress)));
+        choose(&mut state, &mut sessions, 0, Defect).expect("match is running");
+        choose(&mut state, &mut sessions, 1, Defect).expect("match is running");
+        // Match is over
+        assert!(matches!(
+            choose(&mut state, &mut sessions, 0, Defect),
+            Err(IuroError::InvalidInput)
+        ));
+
+        for _ in 0..2 {
+            choose(&mut state, &mut sessions, 2, Cooperate).expect("match is running");
+            choose(&mut state, &mut sessions, 3, Cooperate).expect("match is running");
+        }
+        assert_eq!(state.points[&0], 6);
+        assert_eq!(state.points[&1], 1);
+        assert_eq!(state.points[&2], 6);
+        assert_eq!(state.points[&3], 6);
+        assert_eq!(sessions[&1].wins, 0);
+        assert!([0, 2, 3].iter().all(|id| sessions[id].wins == 1));
+        assert!(matches!(choices(&state, 1)[..], [Cooperate, Defect]));
+    }
+
+    #[test]
+    fn pairs_change_every_rotation() {
+        assert_eq!(pair(vec![3, 1, 2, 0], 0), vec![(0, 3), (1, 2)]);
+        assert_eq!(pair(vec![0, 1, 2, 3], 1), vec![(0, 2), (3, 1)]);
+        // Odd user out sits out
+        assert_eq!(pair(vec![0, 1, 2], 0), vec![(1, 2)]);
+        assert_eq!(pair(vec![0, 1, 2], 1), vec![(0, 2)]);
+        assert!(pair(vec![0], 0).is_empty());
+    }
+}
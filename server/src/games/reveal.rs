@@ -0,0 +1,159 @@
+//! When a game ends every user's input is revealed, along with the correct answer and who won.
+
+use super::{prisoners_dilemma, rock_papiuro_scissor};
+use crate::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, collections::HashMap};
+
+/// Value users submitted, or the correct one
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub enum Answer {
+    Throw(RockPapiuroScissorInput),
+    Values(Vec<u8>),
+    Number(i64),
+    Numbers(Vec<i64>),
+    Text(String),
+    /// Index of the option
+    Option(usize),
+    /// Choices of every iteration, in order
+    Choices(Vec<DilemmaChoice>),
+    /// Cells, or columns, played, in order
+    Moves(Vec<usize>),
+}
+
+/// What a user submitted and how it went
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct PlayerReveal {
+    pub name: String,
+    /// User's last input, or everything they sent in games that stream inputs
+    /// (`TypingRace`, `ButtonMash`, `PrisonersDilemma` and board games), `None` if they sent nothing
+    pub input: Option<Answer>,
+    /// Game specific points earned this game, wins earned if game has no points
+    pub points: usize,
+    pub winner: bool,
+}
+
+/// Every user's input and result in the game that just ended
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Reveal {
+    pub game: Cow<'static, str>,
+    /// Correct answer, if game has one
+    pub answer: Option<Answer>,
+    pub players: Vec<PlayerReveal>,
+}
+
+/// Reveals final state of the game, `wins` are users' wins before it started
+pub fn reveal(
+    state: &GameState,
+    sessions: &HashMap<usize, RoomSlot>,
+    wins: &HashMap<usize, usize>,
+) -> Reveal {
+    let won = |id: &usize| {
+        sessions[id]
+            .wins
+            .saturating_sub(wins.get(id).copied().unwrap_or(0))
+    };
+
+    let answer = match state {
+        GameState::TheRightIuro((target, _)) => target.first().map(|v| Answer::Values(vec![*v])),
+        GameState::MathSprint(state) => Some(Answer::Numbers(
+            state
+                .problems
+                .iter()
+                .map(|p| i64::from(p.answer()))
+                .collect(),
+        )),
+        GameState::IuroSays(state) => Some(Answer::Values(state.sequence.clone())),
+        GameState::WordScramble(state) => Some(Answer::Text(state.word.clone())),
+        GameState::ClosestGuess(state) => Some(Answer::Number(state.answer)),
+        GameState::RockPapiuroScissor(_)
+        | GameState::Majority(_)
+        | GameState::PrisonersDilemma(_)
+        | GameState::TypingRace(_)
+        | GameState::TicTacToe(_)
+        | GameState::ConnectFour(_)
+        | GameState::ButtonMash(_) => None,
+    };
+
+    let rps_points = match state {
+        GameState::RockPapiuroScissor(throws) => rock_papiuro_scissor::points(throws, sessions),
+        _ => HashMap::default(),
+    };
+
+    let mut players: Vec<PlayerReveal> = sessions
+        .iter()
+        .map(|(id, slot)| {
+            let (input, points) = match state {
+                GameState::RockPapiuroScissor(throws) => (
+                    throws.get(id).copied().map(Answer::Throw),
+                    rps_points.get(id).copied().unwrap_or(0),
+                ),
+                GameState::TheRightIuro((target, answers)) => {
+                    let answer = answers.get(id);
+                    let correct = answer.and_then(|a| a.first()) == target.first();
+                    (answer.cloned().map(Answer::Values), correct as usize)
+                }
+                GameState::MathSprint(state) => (
+                    state
+                        .answers
+                        .get(id)
+                        .map(|answer| Answer::Number(i64::from(*answer))),
+                    state.solved.get(id).copied().unwrap_or(0),
+                ),
+                GameState::WordScramble(state) => {
+                    (state.guesses.get(id).cloned().map(Answer::Text), won(id))
+                }
+                GameState::IuroSays(state) => {
+                    (state.answers.get(id).cloned().map(Answer::Values), won(id))
+                }
+                GameState::Majority(state) => {
+                    (state.votes.get(id).copied().map(Answer::Option), won(id))
+                }
+                GameState::PrisonersDilemma(state) => (
+                    Some(Answer::Choices(prisoners_dilemma::choices(state, *id))),
+                    state.points.get(id).copied().unwrap_or(0),
+                ),
+                GameState::TypingRace(state) => {
+                    let typist = state.typists.get(id);
+                    (
+                        typist.map(|typist| Answer::Text(typist.typed.clone())),
+                        typist.map_or(0, |typist| typist.position),
+                    )
+                }
+                GameState::ClosestGuess(state) => (
+                    state
+                        .guesses
+                        .get(id)
+                        .map(|guess| Answer::Number(guess.value)),
+                    won(id),
+                ),
+                GameState::ButtonMash(state) => {
+                    let taps = state.taps.get(id).copied();
+                    (
+                        taps.map(|taps| Answer::Number(i64::from(taps))),
+                        taps.unwrap_or(0) as usize,
+                    )
+                }
+                GameState::TicTacToe(state) | GameState::ConnectFour(state) => {
+                    (state.moves.get(id).cloned().map(Answer::Moves), won(id))
+                }
+            };
+
+            PlayerReveal {
+                name: slot.name.clone(),
+                input,
+                points,
+                winner: won(id) > 0,
+            }
+        })
+        .collect();
+    // Keeps order stable for every user
+    players.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Reveal {
+        game: Cow::Borrowed(state.name()),
+        answer,
+        players,
+    }
+}
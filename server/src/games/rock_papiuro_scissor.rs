@@ -1,10 +1,11 @@
 use crate::prelude::*;
 use actix::prelude::*;
-use serde::{Deserialize};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Input options for `RockPapiuroScissor`
-#[derive(Deserialize, Copy, Clone, Message, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, PartialEq, Message, Debug)]
 pub enum RockPapiuroScissorInput {
     Rock,
     Papiuro,
@@ -13,6 +14,7 @@ pub enum RockPapiuroScissorInput {
 
 impl RockPapiuroScissorInput {
     /// Returns if user wins against other
+    #[allow(clippy::match_like_matches_macro)]
     pub fn beats(self, other: Self) -> bool {
         match (self, other) {
             (Self::Rock, Self::Scissor) => true,
@@ -41,25 +43,13 @@ impl<'a> Update<'a> {
 
         // All users answered
         if state.len() == sessions.len() {
-            // Computes points for each user
-            let points = state.iter().map(|(id, this_input)| {
-                let user_points: u8 = state
-                    .values()
-                    // Beating a user gives 1 point
-                    .map(|other_input| this_input.beats(*other_input) as u8)
-                    .sum();
-                (id, user_points)
-            });
+            let points = points(state, sessions);
 
             // Get winning threshold (more than one user can win)
-            let max = points
-                .clone()
-                .map(|(_id, points)| points)
-                .max()
-                .unwrap_or(1);
+            let max = points.values().copied().max().unwrap_or(1);
 
             // Selects only winning users
-            for (id, _) in points.filter(|(_, points)| *points >= max) {
+            for (id, _) in points.iter().filter(|(_, points)| **points >= max) {
                 let slot = sessions
                     .get_mut(id)
                     // This should never happen
@@ -72,3 +62,63 @@ impl<'a> Update<'a> {
         Ok(ret)
     }
 }
+
+/// Computes points for each user, beating a user gives 1 point
+pub fn points(
+    state: &HashMap<usize, RockPapiuroScissorInput>,
+    sessions: &HashMap<usize, RoomSlot>,
+) -> HashMap<usize, usize> {
+    let throws = team_throws(state, sessions);
+    throws
+        .iter()
+        .map(|(id, this_input)| {
+            let user_points = throws
+                .values()
+                .filter(|other_input| this_input.beats(**other_input))
+                .count();
+            (*id, user_points)
+        })
+        .collect()
+}
+
+/// In team mode every user plays their team's majority throw, otherwise their own
+fn team_throws(
+    state: &HashMap<usize, RockPapiuroScissorInput>,
+    sessions: &HashMap<usize, RoomSlot>,
+) -> HashMap<usize, RockPapiuroScissorInput> {
+    let team = |id: &usize| sessions.get(id).and_then(|slot| slot.team);
+    if state.keys().any(|id| team(id).is_none()) {
+        return state.clone();
+    }
+
+    state
+        .keys()
+        .map(|id| {
+            let throws = state
+                .iter()
+                .filter(|(other, _)| team(other) == team(id))
+                .map(|(_, input)| *input);
+            (*id, majority(throws))
+        })
+        .collect()
+}
+
+/// Most common throw, ties go to the first one in `Rock`, `Papiuro`, `Scissor` order
+fn majority(
+    throws: impl Iterator<Item = RockPapiuroScissorInput> + Clone,
+) -> RockPapiuroScissorInput {
+    let options = [
+        RockPapiuroScissorInput::Rock,
+        RockPapiuroScissorInput::Papiuro,
+        RockPapiuroScissorInput::Scissor,
+    ];
+
+    let mut best = (options[0], 0);
+    for option in &options {
+        let count = throws.clone().filter(|input| input == option).count();
+        if count > best.1 {
+            best = (*option, count);
+        }
+    }
+    best.0
+}
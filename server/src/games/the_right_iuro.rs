@@ -19,10 +19,11 @@ impl<'a> Update<'a> {
 
         // All users answered
         if state.1.len() == sessions.len() {
-            // Computes points for each user
-            let points = state.1.iter().map(|(id, input)| {
-                (id, (&input[0] == &state.0[0]) as u8)
-            });
+            // Computes points for each user, an empty input never matches
+            let points = state
+                .1
+                .iter()
+                .map(|(id, input)| (id, (input.first() == state.0.first()) as u8));
 
             // Selects only winning users
             for (id, _) in points.filter(|(_, points)| *points > 0) {
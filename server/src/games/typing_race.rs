@@ -0,0 +1,339 @@
+//! Users type a passage, streaming what they typed since their last update.
+//!
+//! Updates are rate limited, so users can't flood the server with inputs nor type faster than
+//! humanly possible.
+
+use crate::prelude::*;
+use rand::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+/// How long users have to type the passage
+pub const TIME_LIMIT: Duration = Duration::from_secs(90);
+/// Shortest interval allowed between two updates of the same user
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+/// Most characters a single update may have
+const MAX_CHUNK: usize = 32;
+
+const BUNDLED_PASSAGES: &str = include_str!("../../assets/passages.txt");
+
+/// Picks one of the bundled passages
+pub fn choose<R: Rng + ?Sized>(rng: &mut R) -> String {
+    static PASSAGES: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+    PASSAGES
+        .get_or_init(|| {
+            BUNDLED_PASSAGES
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .collect()
+        })
+        .choose(rng)
+        // Bundled passages are never empty
+        .map_or_else(|| "iuro".to_owned(), |passage| (*passage).to_owned())
+}
+
+/// User's progress in the passage
+#[derive(Debug)]
+pub struct Typist {
+    /// Characters correctly typed
+    pub position: usize,
+    /// Characters that didn't match the passage
+    pub typos: usize,
+    /// Every character received, typos included
+    pub typed: String,
+    /// `None` until the first update, which is never rate limited
+    pub last_update: Option<Instant>,
+    pub finished: Option<Duration>,
+}
+
+/// Passage being typed and each user's progress
+#[derive(Debug)]
+pub struct TypingRaceState {
+    pub passage: Vec<char>,
+    pub started: Instant,
+    pub typists: HashMap<usize, Typist>,
+}
+
+impl TypingRaceState {
+    pub fn new(passage: &str) -> Self {
+        Self {
+            passage: passage.chars().collect(),
+            started: Instant::now(),
+            typists: HashMap::default(),
+        }
+    }
+}
+
+/// User's standing in the race, sent with the game results
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct TypingResult {
+    pub name: String,
+    pub position: usize,
+    /// Percentage of typed characters that were correct
+    pub accuracy: u8,
+    /// Milliseconds it took to finish, if user did finish
+    pub time: Option<u128>,
+}
+
+pub struct Update<'a> {
+    pub user_id: usize,
+    pub input: String,
+    pub state: &'a mut TypingRaceState,
+}
+
+impl<'a> Update<'a> {
+    /// Returns if every user finished the passage
+    pub fn consume(self, sessions: &mut HashMap<usize, RoomSlot>) -> Result<bool, IuroError> {
+        let Update {
+            state,
+            user_id,
+            input,
+        } = self;
+        let now = Instant::now();
+
+        if input.chars().count() > MAX_CHUNK {
+            return Err(IuroError::InvalidInput);
+        }
+
+        let started = state.started;
+        let typist = state.typists.entry(user_id).or_insert_with(|| Typist {
+            position: 0,
+            typos: 0,
+            typed: String::new(),
+            last_update: None,
+            finished: None,
+        });
+
+        if typist.finished.is_some() {
+            return Err(IuroError::InvalidInput);
+        }
+        if let Some(last_update) = typist.last_update {
+            if now.duration_since(last_update) < MIN_INTERVAL {
+                return Err(IuroError::RateLimited);
+            }
+        }
+        typist.last_update = Some(now);
+
+        // Typos don't advance, user must type the right character
+        for typed in input.chars() {
+            typist.typed.push(typed);
+            if state.passage.get(typist.position) == Some(&typed) {
+                typist.position += 1;
+            } else {
+                typist.typos += 1;
+            }
+
+            if typist.position == state.passage.len() {
+                typist.finished = Some(now.duration_since(started));
+                break;
+            }
+        }
+
+        let finished = state
+            .typists
+            .values()
+            .filter(|typist| typist.finished.is_some())
+            .count();
+        if finished < sessions.len() {
+            return Ok(false);
+        }
+
+        award(state, sessions)?;
+        Ok(true)
+    }
+}
+
+/// Ranks users by finish time, and accuracy if they finished at the same time or didn't finish
+fn rank(state: &TypingRaceState) -> Vec<(usize, &Typist)> {
+    let mut ranking: Vec<(usize, &Typist)> = state
+        .typists
+        .iter()
+        .map(|(id, typist)| (*id, typist))
+        .collect();
+
+    ranking.sort_by(|(_, a), (_, b)| {
+        let time = |typist: &Typist| typist.finished.unwrap_or(Duration::from_secs(u64::MAX));
+        time(a)
+            .cmp(&time(b))
+            .then(b.position.cmp(&a.position))
+            .then(a.typos.cmp(&b.typos))
+    });
+    ranking
+}
+
+/// Gives a win to the first in the ranking, or all of them if tied
+fn award(
+    state: &TypingRaceState,
+    sessions: &mut HashMap<usize, RoomSlot>,
+) -> Result<(), IuroError> {
+    let ranking = rank(state);
+    let first = match ranking.first() {
+        // Users that typed nothing don't win
+        Some((_, typist)) if typist.position > 0 => *typist,
+        _ => return Ok(()),
+    };
+
+    for (id, typist) in &ranking {
+        if typist.finished != first.finished
+            || typist.position != first.position
+            || typist.typos != first.typos
+        {
+            break;
+        }
+
+        let slot = sessions
+            .get_mut(id)
+            // This should never happen
+            .ok_or(IuroError::AddrNotFound(*id))?;
+
+        slot.wins += 1;
+    }
+    Ok(())
+}
+
+/// Time is up, users are ranked by how far they got
+pub fn timeout(
+    state: &TypingRaceState,
+    sessions: &mut HashMap<usize, RoomSlot>,
+) -> Result<(), IuroError> {
+    award(state, sessions)
+}
+
+/// Characters each user correctly typed so far
+pub fn progress(
+    state: &TypingRaceState,
+    sessions: &HashMap<usize, RoomSlot>,
+) -> HashMap<String, usize> {
+    sessions
+        .iter()
+        .map(|(id, slot)| {
+            let position = state.typists.get(id).map_or(0, |typist| typist.position);
+            (slot.name.clone(), position)
+        })
+        .collect()
+}
+
+/// Final ranking of the race
+pub fn results(state: &TypingRaceState, sessions: &HashMap<usize, RoomSlot>) -> Vec<TypingResult> {
+    rank(state)
+        .into_iter()
+        .filter_map(|(id, typist)| {
+            let typed = typist.position + typist.typos;
+            // Nothing typed means no accuracy
+            let accuracy = (typist.position * 100).checked_div(typed).unwrap_or(0) as u8;
+
+            sessions.get(&id).map(|slot| TypingResult {
+                name: slot.name.clone(),
+                position: typist.position,
+                accuracy,
+                time: typist.finished.map(|time| time.as_millis()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sessions;
+    use actix::System;
+
+    fn send(
+        state: &mut TypingRaceState,
+        sessions: &mut HashMap<usize, RoomSlot>,
+        user_id: usize,
+        input: &str,
+    ) -> Result<bool, IuroError> {
+        let update = Update {
+            user_id,
+            input: input.to_owned(),
+            state,
+        };
+        update.consume(sessions)
+    }
+
+    #[test]
+    fn rejects_oversized_and_too_frequent_updates() {
+        let _system = System::new("test");
+        let mut sessions = sessions(2);
+        let mut state = TypingRaceState::new("the quick brown fox");
+
+        let chunk = "t".repeat(MAX_CHUNK + 1);
+        assert!(matches!(
+            send(&mut state, &mut sessions, 0, &chunk),
+            Err(IuroError::InvalidInput)
+        ));
+        // Rejected chunk wasn't the first update
+        assert!(matches!(
+            send(&mut state, &mut sessions, 0, "the"),
+            Ok(false)
+        ));
+        assert!(matches!(
+            send(&mut state, &mut sessions, 0, " q"),
+            Err(IuroError::RateLimited)
+        ));
+        assert_eq!(state.typists[&0].position, 3);
+    }
+
+    #[test]
+    fn typos_never_advance() {
+        let _system = System::new("test");
+        let mut sessions = sessions(1);
+        let mut state = TypingRaceState::new("iuro");
+        assert!(matches!(
+            send(&mut state, &mut sessions, 0, "ixu"),
+            Ok(false)
+        ));
+
+        let typist = &state.typists[&0];
+        assert_eq!((typist.position, typist.typos), (2, 1));
+        assert_eq!(typist.typed, "ixu");
+        assert_eq!(results(&state, &sessions)[0].accuracy, 66);
+    }
+
+    #[test]
+    fn race_ends_once_everybody_finished() {
+        let _system = System::new("test");
+        let mut sessions = sessions(2);
+        let mut state = TypingRaceState::new("iuro");
+        assert!(matches!(
+            send(&mut state, &mut sessions, 0, "iuro"),
+            Ok(false)
+        ));
+        assert!(matches!(
+            send(&mut state, &mut sessions, 1, "iuro"),
+            Ok(true)
+        ));
+        assert!(sessions.values().any(|slot| slot.wins == 1));
+        // Finished typists can't type anymore
+        assert!(send(&mut state, &mut sessions, 0, "x").is_err());
+    }
+
+    #[test]
+    fn furthest_typist_wins_when_time_is_up() {
+        let _system = System::new("test");
+        let mut sessions = sessions(3);
+        let mut state = TypingRaceState::new("the quick brown fox");
+        let typist = |position, typos| Typist {
+            position,
+            typos,
+            typed: String::new(),
+            last_update: None,
+            finished: None,
+        };
+        state.typists.insert(0, typist(5, 2));
+        state.typists.insert(1, typist(5, 0));
+        state.typists.insert(2, typist(3, 0));
+
+        timeout(&state, &mut sessions).expect("users are in the room");
+        assert_eq!(sessions[&1].wins, 1);
+        assert_eq!(sessions[&0].wins + sessions[&2].wins, 0);
+    }
+}
@@ -0,0 +1,136 @@
+//! Users must unscramble a word, any anagram of it present in the dictionary is accepted.
+//!
+//! The dictionary is bundled with the server, but a different one can be configured by
+//! pointing `IURO_DICTIONARY` to a file with one word per line.
+
+use crate::prelude::*;
+use rand::prelude::*;
+use std::{collections::HashMap, collections::HashSet, fs, sync::OnceLock, time::Duration};
+
+/// How long users have to unscramble the word, nobody wins if time is up
+pub const TIME_LIMIT: Duration = Duration::from_secs(60);
+/// Shortest word that may be scrambled, shorter ones are only used as accepted anagrams
+const MIN_LENGTH: usize = 4;
+
+const BUNDLED_DICTIONARY: &str = include_str!("../../assets/words.txt");
+
+/// Words that can be scrambled and accepted as answers
+#[derive(Debug)]
+pub struct Dictionary {
+    words: Vec<String>,
+    known: HashSet<String>,
+}
+
+impl Dictionary {
+    /// Parses one word per line, lines that aren't a single alphabetic word are ignored
+    pub fn parse(text: &str) -> Self {
+        let known: HashSet<String> = text
+            .lines()
+            .map(|line| line.trim().to_lowercase())
+            .filter(|word| !word.is_empty() && word.chars().all(char::is_alphabetic))
+            .collect();
+
+        let mut words: Vec<String> = known
+            .iter()
+            .filter(|word| word.chars().count() >= MIN_LENGTH)
+            .cloned()
+            .collect();
+        // Keeps word choice deterministic for a seeded rng
+        words.sort();
+
+        Self { words, known }
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.known.contains(word)
+    }
+
+    /// Picks a word to be scrambled
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&str> {
+        self.words.choose(rng).map(String::as_str)
+    }
+}
+
+/// Loads dictionary configured by `IURO_DICTIONARY`, falling back to the bundled one
+pub fn dictionary() -> &'static Dictionary {
+    static DICTIONARY: OnceLock<Dictionary> = OnceLock::new();
+
+    DICTIONARY.get_or_init(|| {
+        let dictionary = match std::env::var("IURO_DICTIONARY") {
+            Ok(path) => match fs::read_to_string(&path) {
+                Ok(text) => Dictionary::parse(&text),
+                Err(err) => {
+                    error!("Unable to read dictionary {}: {}", path, err);
+                    Dictionary::parse(BUNDLED_DICTIONARY)
+                }
+            },
+            Err(_) => Dictionary::parse(BUNDLED_DICTIONARY),
+        };
+
+        if dictionary.words.is_empty() {
+            error!("Dictionary has no word to scramble, using bundled one");
+            return Dictionary::parse(BUNDLED_DICTIONARY);
+        }
+        dictionary
+    })
+}
+
+/// Shuffles word's letters, avoiding returning the word itself when possible
+pub fn scramble<R: Rng + ?Sized>(word: &str, rng: &mut R) -> String {
+    let mut letters: Vec<char> = word.chars().collect();
+    for _ in 0..8 {
+        letters.shuffle(rng);
+        let scrambled: String = letters.iter().collect();
+        if scrambled != word {
+            return scrambled;
+        }
+    }
+    letters.into_iter().collect()
+}
+
+/// Letters of the word, in order, to compare anagrams
+fn letters(word: &str) -> Vec<char> {
+    let mut letters: Vec<char> = word.chars().collect();
+    letters.sort_unstable();
+    letters
+}
+
+/// Word to be guessed and each user's last guess
+#[derive(Debug)]
+pub struct WordScrambleState {
+    pub word: String,
+    pub guesses: HashMap<usize, String>,
+}
+
+pub struct Update<'a> {
+    pub user_id: usize,
+    pub input: String,
+    pub state: &'a mut WordScrambleState,
+}
+
+impl<'a> Update<'a> {
+    pub fn consume(self, sessions: &mut HashMap<usize, RoomSlot>) -> Result<bool, IuroError> {
+        let Update {
+            state,
+            user_id,
+            input,
+        } = self;
+        let input = input.trim().to_lowercase();
+        state.guesses.insert(user_id, input.clone());
+        let word = state.word.as_str();
+
+        // Wrong guesses are ignored, users may keep trying until someone gets it
+        if letters(&input) != letters(word) || !(input == word || dictionary().contains(&input)) {
+            return Ok(false);
+        }
+
+        // First user to unscramble the word wins
+        let slot = sessions
+            .get_mut(&user_id)
+            // This should never happen
+            .ok_or(IuroError::AddrNotFound(user_id))?;
+
+        slot.wins += 1;
+        Ok(true)
+    }
+}
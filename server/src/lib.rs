@@ -1,34 +1,90 @@
+mod bots;
 mod error;
 mod games;
 mod messages;
+pub mod ndjson;
+mod phase;
+mod playlist;
+mod protocol;
 mod room;
+pub mod schema;
 mod server;
 mod session;
+mod sse;
+#[cfg(test)]
+mod testing;
+mod websocket;
 
 pub use crate::error::IuroError;
 pub use crate::server::IuroServer;
+pub use crate::sse::{sse_command, sse_heartbeat, sse_route, SseSessions};
+pub use crate::websocket::iuro_route;
 
 use actix::prelude::*;
-use actix_web::{web, HttpRequest, HttpResponse};
-use actix_web_actors::ws;
-use log::error;
-use rand::random;
-use serde_json::{from_str, to_string};
-use std::{borrow::Cow, time::Instant};
+use std::borrow::Cow;
 
+/// Types exchanged with clients, see `protocol` for how they're encoded
+///
+/// Also has `grid_cells`, clients generate `Estimate::Grid` with it to show the same grid
+pub mod types {
+    pub use crate::bots::{BotSettings, ThrowStrategy};
+    pub use crate::games::prelude::{
+        grid_cells, BoardView, DilemmaChoice, Estimate, Game, GameInput, GameKind, GameProgress,
+        GameResults, GuessResults, MashResults, MathProblem, Prompt, Reveal,
+        RockPapiuroScissorInput, TypingResult, VoteSplit,
+    };
+    pub use crate::games::{
+        closest_guess::GuessResult,
+        math_sprint::MathOperator,
+        reveal::{Answer, PlayerReveal},
+    };
+    pub use crate::messages::{Command, Response};
+    pub use crate::phase::{MatchSettings, Phase};
+    pub use crate::playlist::Playlist;
+    pub use crate::room::{RosterEntry, TeamStanding};
+}
+
+// Kept in its original order
+#[rustfmt::skip]
 mod prelude {
+    pub use crate::bots::{Bot, BotSettings};
     pub use crate::messages::{Response, *};
-    pub use crate::room::{Room, RoomSlot};
+    pub use crate::phase::{MatchSettings, Phase};
+    pub use crate::playlist::Playlist;
+    pub use crate::room::{Room, RoomSlot, RosterEntry, TeamStanding};
     pub use crate::session::IuroSession;
     pub use crate::{IuroError, IuroServer};
     pub use crate::games::prelude::*;
+    #[allow(unused_imports)]
     pub use log::{debug, error, info, trace, warn};
 }
 
 use crate::prelude::{Response, *};
 
 pub fn handle_text(msg: &str, act: &mut IuroSession, ctx: &mut Ctx) -> Result<(), IuroError> {
-    match from_str(&msg)? {
+    handle_incoming(protocol::decode(msg)?, act, ctx)
+}
+
+/// Same as `handle_text`, for sessions that chose MessagePack
+pub fn handle_binary(raw: &[u8], act: &mut IuroSession, ctx: &mut Ctx) -> Result<(), IuroError> {
+    handle_incoming(protocol::decode_binary(raw)?, act, ctx)
+}
+
+fn handle_incoming(
+    incoming: protocol::Incoming,
+    act: &mut IuroSession,
+    ctx: &mut Ctx,
+) -> Result<(), IuroError> {
+    let command = match incoming {
+        protocol::Incoming::Hello(requested) => {
+            act.protocol = protocol::negotiate(requested);
+            act.respond(&Response::Welcome(act.protocol), ctx);
+            return Ok(());
+        }
+        protocol::Incoming::Command(command) => command,
+    };
+
+    match command {
         Command::ListRooms => {
             let future = send(act, ListRooms).map(Response::Rooms);
             spawn(future.into_actor(act), ctx);
@@ -70,6 +126,146 @@ pub fn handle_text(msg: &str, act: &mut IuroSession, ctx: &mut Ctx) -> Result<()
                 return Err(IuroError::MustJoinRoom);
             }
         }
+        Command::TeamMode(enabled) => {
+            let room = act.room.as_ref().ok_or(IuroError::MustJoinRoom)?.clone();
+            let future = send(
+                act,
+                SetTeamMode {
+                    id: act.id,
+                    room,
+                    enabled,
+                },
+            )
+            .and_then(|r| r)
+            // Teams are broadcasted on success
+            .map(|_| None)
+            .into_actor(act);
+            spawn(future, ctx);
+        }
+        Command::Team(team) => {
+            let data = ChooseTeam {
+                id: act.id,
+                room: act.room.as_ref().ok_or(IuroError::MustJoinRoom)?.clone(),
+                team,
+            };
+            let future = send(act, data)
+                .and_then(|r| r)
+                // Teams are broadcasted on success
+                .map(|_| None)
+                .into_actor(act);
+            spawn(future, ctx);
+        }
+        Command::TeamMessage(msg) => {
+            if let Some(room) = act.room.clone() {
+                let cmd = TeamChatMessage {
+                    id: act.id,
+                    msg,
+                    room,
+                };
+
+                // Send message to `IuroServer` broadcast to user's team
+                act.addr.do_send(cmd);
+            } else {
+                return Err(IuroError::MustJoinRoom);
+            }
+        }
+        Command::Playlist(playlist) => {
+            let data = SetPlaylist {
+                id: act.id,
+                room: act.room.as_ref().ok_or(IuroError::MustJoinRoom)?.clone(),
+                playlist,
+            };
+            let future = send(act, data)
+                .and_then(|r| r)
+                // Queue is broadcasted on success
+                .map(|_| None)
+                .into_actor(act);
+            spawn(future, ctx);
+        }
+        cmd @ (Command::Pause | Command::Resume) => {
+            let data = SetPaused {
+                id: act.id,
+                room: act.room.as_ref().ok_or(IuroError::MustJoinRoom)?.clone(),
+                paused: matches!(cmd, Command::Pause),
+            };
+            let future = send(act, data)
+                .and_then(|r| r)
+                // Phase is broadcasted on success
+                .map(|_| None)
+                .into_actor(act);
+            spawn(future, ctx);
+        }
+        Command::Settings(settings) => {
+            let data = SetMatchSettings {
+                id: act.id,
+                room: act.room.as_ref().ok_or(IuroError::MustJoinRoom)?.clone(),
+                settings,
+            };
+            let future = send(act, data)
+                .and_then(|r| r)
+                .map(|_| Response::Text(Cow::Borrowed("Match settings changed")))
+                .into_actor(act);
+            spawn(future, ctx);
+        }
+        Command::Skip => {
+            let data = VoteSkip {
+                id: act.id,
+                room: act.room.as_ref().ok_or(IuroError::MustJoinRoom)?.clone(),
+            };
+            let future = send(act, data)
+                .and_then(|r| r)
+                // Votes are broadcasted on success
+                .map(|_| None)
+                .into_actor(act);
+            spawn(future, ctx);
+        }
+        Command::Vote(candidate) => {
+            let data = VoteNext {
+                id: act.id,
+                room: act.room.as_ref().ok_or(IuroError::MustJoinRoom)?.clone(),
+                candidate,
+            };
+            let future = send(act, data)
+                .and_then(|r| r)
+                // Votes are broadcasted on success
+                .map(|_| None)
+                .into_actor(act);
+            spawn(future, ctx);
+        }
+        Command::AddBot(settings) => {
+            let data = AddBot {
+                id: act.id,
+                room: act.room.as_ref().ok_or(IuroError::MustJoinRoom)?.clone(),
+                settings,
+            };
+            let future = send(act, data)
+                .and_then(|r| r)
+                // Roster is broadcasted on success
+                .map(|_| None)
+                .into_actor(act);
+            spawn(future, ctx);
+        }
+        Command::RemoveBot(name) => {
+            let data = RemoveBot {
+                id: act.id,
+                room: act.room.as_ref().ok_or(IuroError::MustJoinRoom)?.clone(),
+                name,
+            };
+            let future = send(act, data)
+                .and_then(|r| r)
+                // Roster is broadcasted on success
+                .map(|_| None)
+                .into_actor(act);
+            spawn(future, ctx);
+        }
+        Command::Game(GameInput::ButtonMash(taps)) => {
+            if act.room.is_none() {
+                return Err(IuroError::MustJoinRoom);
+            }
+
+            // Taps are batched to avoid flooding `IuroServer`, see `IuroSession::flush_taps`
+            act.taps = act.taps.saturating_add(taps);
+        }
         Command::Game(games) => {
             let input = UserGameInput {
                 id: act.id,
@@ -88,25 +284,7 @@ pub fn handle_text(msg: &str, act: &mut IuroSession, ctx: &mut Ctx) -> Result<()
     Ok(())
 }
 
-type Ctx = ws::WebsocketContext<IuroSession>;
-
-/// Entry point for our route
-pub fn iuro_route(
-    req: HttpRequest,
-    stream: web::Payload,
-    srv: web::Data<Addr<IuroServer>>,
-) -> Result<HttpResponse, actix_web::Error> {
-    let session = IuroSession {
-        // This is not ideal since `ThreadRng` is not cached,
-        // but it's better than needing an `Actor` to generate an id
-        id: random(),
-        heartbeat: Instant::now(),
-        room: None,
-        addr: srv.get_ref().clone(),
-    };
-    // Upgrades connection to websocket
-    ws::start(session, &req, stream)
-}
+type Ctx = Context<IuroSession>;
 
 /// Abstracts sending message to `IuroServer` and actix error handling
 fn send<M>(act: &mut IuroSession, cmd: M) -> impl Future<Item = M::Result, Error = IuroError>
@@ -119,7 +297,7 @@ where
     act.addr.send(cmd).from_err()
 }
 
-/// Spawns async task with specified future, sending its result with websocket
+/// Spawns async task with specified future, sending its result to the client
 ///
 /// If `None` is passed in `Item` no message is sent on success
 fn spawn(
@@ -127,26 +305,15 @@ fn spawn(
         + 'static,
     ctx: &mut Ctx,
 ) {
-    fut.then(|res, _, ctx| {
-        let json = match res {
+    fut.then(|res, act, ctx| {
+        match res {
             Ok(res) => {
                 // If `None` is passed no message is sent on success
                 if let Some(res) = res.into() {
-                    to_string(&res)
-                } else {
-                    return fut::ok(());
+                    act.respond(&res, ctx);
                 }
             }
-            Err(err) => to_string(&Response::Error(err.to_string())),
-        };
-
-        if let Ok(json) = json {
-            trace!("Sending: {}", json);
-            ctx.text(json);
-        } else {
-            // This should never happen
-            error!("Failed to serialize `Response`");
-            debug_assert!(false, "Failed to serialize `Response`");
+            Err(err) => act.respond(&Response::Error(err.to_string()), ctx),
         }
         fut::ok(())
     })
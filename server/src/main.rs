@@ -2,20 +2,49 @@ use actix::prelude::*;
 use actix_files as fs;
 use actix_web::{web, App, HttpResponse, HttpServer};
 
-use iuro_server::{iuro_route, IuroServer};
+use iuro_server::{
+    iuro_route, ndjson, schema, sse_command, sse_heartbeat, sse_route, IuroServer, SseSessions,
+};
+use std::{env, path::Path};
 
 fn main() {
     env_logger::init();
 
+    // Regenerates the protocol's JSON Schemas and TypeScript typings instead of serving
+    if env::args().nth(1).as_deref() == Some("schema") {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schema");
+        schema::write(&dir).expect("Unable to write schemas");
+        return;
+    }
+
     let sys = System::new("iuro-server");
     // Start iuro server actor
     let server = IuroServer::default().start();
 
+    // Optional listeners speaking newline-delimited JSON, for bots and tooling
+    if let Ok(addr) = env::var("IURO_TCP") {
+        ndjson::listen_tcp(&addr, server.clone()).expect("Unable to bind TCP listener");
+    }
+    #[cfg(unix)]
+    {
+        if let Ok(path) = env::var("IURO_UNIX") {
+            ndjson::listen_unix(&path, server.clone()).expect("Unable to bind Unix socket");
+        }
+    }
+
+    // Shared by every worker, so SSE commands reach sessions started by any of them
+    let sse_sessions = web::Data::new(SseSessions::default());
+
     // Create Http server with websocket support
     HttpServer::new(move || {
         let app = App::new()
             .data(server.clone())
-            .service(web::resource("/ws/").to(iuro_route));
+            .register_data(sse_sessions.clone())
+            .service(web::resource("/ws/").to(iuro_route))
+            // Fallback for clients whose proxies break websockets
+            .service(web::resource("/sse/").route(web::get().to(sse_route)))
+            .service(web::resource("/sse/{id}").route(web::post().to(sse_command)))
+            .service(web::resource("/sse/{id}/heartbeat").route(web::post().to(sse_heartbeat)));
 
         // Debug interface
         #[cfg(debug_assertions)]
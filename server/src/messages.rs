@@ -1,11 +1,14 @@
 use crate::prelude::*;
 use actix::prelude::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, collections::HashMap, time::Duration};
 
 /// All messages that can be sent to user (serialized)
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub enum Response {
+    /// Protocol version the server will speak, reply to the client's hello
+    Welcome(u32),
     /// List of rooms
     Rooms(Vec<String>),
     /// Chat message
@@ -14,12 +17,40 @@ pub enum Response {
     Error(String),
     /// Which game is starting
     GameStarted(Game),
-    /// Returns map of users and their wins
-    GameEnded((&'static str, HashMap<String, usize>)),
+    /// Returns progress of current game and users that already answered its round
+    GameProgress(GameProgress),
+    /// Next sequence to be repeated and users still playing
+    SequenceRevealed((Vec<u8>, Vec<String>)),
+    /// Board changed after a move
+    BoardUpdated(BoardView),
+    /// Next games to be played
+    NextUp(Vec<GameKind>),
+    /// Members and wins of each team, empty if room isn't in team mode
+    Teams(Vec<TeamStanding>),
+    /// Users in the room, their teams and round-trip times
+    Roster(Vec<RosterEntry>),
+    /// Room's new phase and how many milliseconds it lasts, `None` if it isn't timed
+    PhaseChanged((Phase, Option<u64>)),
+    /// How many users voted to skip current game, and how many votes are needed
+    SkipVotes((usize, usize)),
+    /// Games that may be played next, the first one is played if nobody votes
+    NextCandidates(Vec<GameKind>),
+    /// Votes each candidate has
+    NextVotes(Vec<usize>),
+    /// Every user's input, points and if they won, along with the correct answer (if any)
+    GameRevealed(Reveal),
+    /// Returns map of users and their wins, and game specific results (if any)
+    GameEnded(
+        (
+            Cow<'static, str>,
+            HashMap<String, usize>,
+            Option<GameResults>,
+        ),
+    ),
 }
 
 /// Commands sent from client (to be deserialized)
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub enum Command {
     /// Returns all existing rooms -> '"ListRooms"'
     ListRooms,
@@ -29,12 +60,37 @@ pub enum Command {
     Name(String),
     /// Message to be multicasted to all users in same room as sender, except the sender '{ "Message": <string> }'
     Message(String),
-    /// Game input sent from user { "Game": { "RockPapiuroScissor": "Rock" } }
+    /// Splits room's users in teams, or stops doing so, only for the room's host and before the game starts '{ "TeamMode": <bool> }'
+    TeamMode(bool),
+    /// Moves user to a team, only before the game starts '{ "Team": <number> }'
+    Team(usize),
+    /// Message to be multicasted to all users in the sender's team '{ "TeamMessage": <string> }'
+    TeamMessage(String),
+    /// Changes which games the room plays, only for the room's host
+    /// '{ "Playlist": { "weights": { "MathSprint": 2, "TicTacToe": 1 }, "no_repeat": true, "order": [] } }'
+    Playlist(Playlist),
+    /// Freezes room's timers, only for the room's host '"Pause"'
+    Pause,
+    /// Unfreezes room's timers, only for the room's host '"Resume"'
+    Resume,
+    /// Changes phase durations (in milliseconds) and games per match, only for the room's host
+    /// '{ "Settings": { "countdown": 3000, "results": 15000, "match_over": 10000, "games": 10 } }'
+    Settings(MatchSettings),
+    /// Votes to skip current game, most users must vote for it to end without winners '"Skip"'
+    Skip,
+    /// Votes for one of the candidates to be played next, only between games '{ "Vote": <number> }'
+    Vote(usize),
+    /// Adds a bot playing with the given strategies, only for the room's host
+    /// '{ "AddBot": { "rock_papiuro_scissor": "Adaptive", "recall": 0.75 } }'
+    AddBot(BotSettings),
+    /// Removes a bot by its name, only for the room's host '{ "RemoveBot": <string> }'
+    RemoveBot(String),
+    /// Game input sent from user '{ "Game": { "RockPapiuroScissor": "Rock" } }' or '{ "Game": { "MathSprint": 42 } }'
     Game(GameInput),
 }
 
 /// Attach user to its `GameInput`
-#[derive(Deserialize, Message, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Message, Debug)]
 #[rtype("Result<(), IuroError>")]
 pub struct UserGameInput {
     pub id: usize,
@@ -51,8 +107,37 @@ pub enum Broadcast {
     Literal(&'static str),
     /// Game that has just started, with its data
     GameStarted(Game),
-    /// Game ended, returns map of users and their winnings
-    GameEnded((&'static str, HashMap<String, usize>)),
+    /// Game is still running, returns users' progress and who already answered
+    GameProgress(GameProgress),
+    /// Game started a new round, returns sequence to be repeated and users still playing
+    SequenceRevealed((Vec<u8>, Vec<String>)),
+    /// User made a move, returns the board it changed
+    BoardUpdated(BoardView),
+    /// Queue changed, returns next games to be played
+    NextUp(Vec<GameKind>),
+    /// Teams changed, returns members and wins of each team
+    Teams(Vec<TeamStanding>),
+    /// Users, their teams or round-trip times changed, returns every user in the room
+    Roster(Vec<RosterEntry>),
+    /// Room changed phase, returns it and how many milliseconds it lasts (if timed)
+    PhaseChanged((Phase, Option<u64>)),
+    /// User voted to skip current game, returns how many voted and how many votes are needed
+    SkipVotes((usize, usize)),
+    /// Vote for the next game started, returns the candidates
+    NextCandidates(Vec<GameKind>),
+    /// User voted for the next game, returns votes of each candidate
+    NextVotes(Vec<usize>),
+    /// Game ended, returns what every user submitted and how it went
+    GameRevealed(Reveal),
+    /// Game ended, returns map of users and their winnings, and game specific results (if any)
+    GameEnded((&'static str, HashMap<String, usize>, Option<GameResults>)),
+}
+
+/// Timed phase ended, `timer` identifies which phase it was set for
+#[derive(Message, Debug)]
+pub struct PhaseTimeout {
+    pub room: String,
+    pub timer: usize,
 }
 
 /// Creates new session
@@ -69,6 +154,14 @@ pub struct Disconnect {
     pub id: usize,
 }
 
+/// Client's transport measured its round-trip time
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct UpdateRtt {
+    pub id: usize,
+    pub rtt: Duration,
+}
+
 /// Set user's name
 #[derive(Message, Debug)]
 #[rtype("Result<(), IuroError>")]
@@ -92,6 +185,95 @@ pub struct ChatMessage {
     pub room: String,
 }
 
+/// Splits room in teams, or stops doing so, only the host can do it
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct SetTeamMode {
+    pub id: usize,
+    pub room: String,
+    pub enabled: bool,
+}
+
+/// Moves user to a team
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct ChooseTeam {
+    pub id: usize,
+    pub room: String,
+    pub team: usize,
+}
+
+/// Sends message to sender's team
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct TeamChatMessage {
+    pub id: usize,
+    pub msg: String,
+    pub room: String,
+}
+
+/// Changes room's playlist
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct SetPlaylist {
+    pub id: usize,
+    pub room: String,
+    pub playlist: Playlist,
+}
+
+/// Pauses or resumes room
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct SetPaused {
+    pub id: usize,
+    pub room: String,
+    pub paused: bool,
+}
+
+/// Changes room's match settings
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct SetMatchSettings {
+    pub id: usize,
+    pub room: String,
+    pub settings: MatchSettings,
+}
+
+/// Adds bot to room
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct AddBot {
+    pub id: usize,
+    pub room: String,
+    pub settings: BotSettings,
+}
+
+/// Removes bot from room
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct RemoveBot {
+    pub id: usize,
+    pub room: String,
+    pub name: String,
+}
+
+/// Votes to skip current game
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct VoteSkip {
+    pub id: usize,
+    pub room: String,
+}
+
+/// Votes for the next game
+#[derive(Message, Debug)]
+#[rtype("Result<(), IuroError>")]
+pub struct VoteNext {
+    pub id: usize,
+    pub room: String,
+    pub candidate: usize,
+}
+
 /// Join room, if room does not exists create new one, leave other rooms. Starts game if room is full
 #[derive(Message, Debug)]
 #[rtype("Result<(), IuroError>")]
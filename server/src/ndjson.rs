@@ -0,0 +1,159 @@
+//! Newline-delimited JSON over raw TCP or Unix sockets, for bots and tooling
+//!
+//! Every line sent is a `Command` and every `Response` is written as a line, in the same
+//! formats websocket clients use. Any line, even an empty one, counts as a heartbeat.
+
+use crate::prelude::*;
+use crate::protocol::{Encoding, Frame};
+use crate::session::ClientMessage;
+use actix::{io::FramedWrite, io::WriteHandler, prelude::*};
+use futures::sync::mpsc::unbounded;
+use std::{io, net::ToSocketAddrs};
+use tokio_codec::{FramedRead, LinesCodec};
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_tcp::TcpListener;
+
+/// Longest line accepted, the same as the largest frame websocket clients may send
+const MAX_LINE: usize = 65_536;
+
+/// Relays lines between a socket and its `IuroSession`
+pub struct LineConnection<W: AsyncWrite + 'static> {
+    /// Session is started along with the connection
+    session: Option<Addr<IuroSession>>,
+    addr: Addr<IuroServer>,
+    writer: FramedWrite<W, LinesCodec>,
+}
+
+impl<W: AsyncWrite + 'static> Actor for LineConnection<W> {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let (sender, receiver) = unbounded();
+        let session = IuroSession::new(self.addr.clone(), Encoding::Json, sender);
+        self.session = Some(session.start());
+        ctx.add_stream(receiver);
+    }
+
+    fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        self.send(ClientMessage::Close);
+        Running::Stop
+    }
+}
+
+impl<W: AsyncWrite + 'static> LineConnection<W> {
+    fn send(&self, msg: ClientMessage) {
+        if let Some(session) = &self.session {
+            session.do_send(msg);
+        }
+    }
+}
+
+/// Lines sent by the client
+impl<W: AsyncWrite + 'static> StreamHandler<String, io::Error> for LineConnection<W> {
+    fn handle(&mut self, line: String, _: &mut Self::Context) {
+        if line.trim().is_empty() {
+            self.send(ClientMessage::Heartbeat);
+        } else {
+            self.send(ClientMessage::Text(line));
+        }
+    }
+
+    /// Lines that are too long or aren't UTF-8 can't be recovered from, so the socket is closed
+    fn error(&mut self, err: io::Error, _: &mut Self::Context) -> Running {
+        warn!("Closing line connection: {}", err);
+        Running::Stop
+    }
+}
+
+/// Writes session's frames as lines, closing the socket once session stops
+impl<W: AsyncWrite + 'static> StreamHandler<Frame, ()> for LineConnection<W> {
+    fn handle(&mut self, frame: Frame, _: &mut Self::Context) {
+        match frame {
+            // Serialized JSON never has line breaks, so every response fits in one line
+            Frame::Text(text) => self.writer.write(text),
+            // Sessions of line clients always use JSON
+            Frame::Binary(_) => (),
+        }
+    }
+
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+impl<W: AsyncWrite + 'static> WriteHandler<io::Error> for LineConnection<W> {}
+
+/// Starts a connection speaking newline-delimited JSON over `stream`
+fn connect<S: AsyncRead + AsyncWrite + 'static>(stream: S, addr: Addr<IuroServer>) {
+    LineConnection::create(move |ctx| {
+        let (reader, writer) = stream.split();
+        ctx.add_stream(FramedRead::new(
+            reader,
+            LinesCodec::new_with_max_length(MAX_LINE),
+        ));
+        LineConnection {
+            session: None,
+            addr,
+            writer: FramedWrite::new(writer, LinesCodec::new(), ctx),
+        }
+    });
+}
+
+/// Accepts TCP connections on `addr`, e.g. `127.0.0.1:8081`
+pub fn listen_tcp(addr: &str, server: Addr<IuroServer>) -> io::Result<()> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No address to bind"))?;
+    let listener = TcpListener::bind(&addr)?;
+
+    Arbiter::spawn(
+        listener
+            .incoming()
+            // A failed connection must not stop the listener
+            .then(|stream| {
+                if let Err(err) = &stream {
+                    error!("Unable to accept TCP connection: {}", err);
+                }
+                Ok::<_, ()>(stream.ok())
+            })
+            .filter_map(|stream| stream)
+            .for_each(move |stream| {
+                connect(stream, server.clone());
+                Ok(())
+            }),
+    );
+    Ok(())
+}
+
+/// Accepts Unix socket connections on `path`, replacing a stale socket left there
+#[cfg(unix)]
+pub fn listen_unix(path: &str, server: Addr<IuroServer>) -> io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+    use tokio_uds::UnixListener;
+
+    if let Ok(metadata) = std::fs::metadata(path) {
+        if metadata.file_type().is_socket() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    let listener = UnixListener::bind(path)?;
+
+    Arbiter::spawn(
+        listener
+            .incoming()
+            // A failed connection must not stop the listener
+            .then(|stream| {
+                if let Err(err) = &stream {
+                    error!("Unable to accept Unix socket connection: {}", err);
+                }
+                Ok::<_, ()>(stream.ok())
+            })
+            .filter_map(|stream| stream)
+            .for_each(move |stream| {
+                connect(stream, server.clone());
+                Ok(())
+            }),
+    );
+    Ok(())
+}
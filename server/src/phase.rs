@@ -0,0 +1,122 @@
+//! Rooms go through phases, the timed ones end when an `IuroServer` timer fires
+
+use crate::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Longest a timed phase may last, in milliseconds, the server's timers can't wait for years
+const MAX_PHASE: u64 = 10 * 60 * 1000;
+
+/// What a room is doing
+#[derive(Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq)]
+pub enum Phase {
+    /// Waiting for the room to be full
+    Lobby,
+    /// Next game is about to start
+    Countdown,
+    /// Game is running
+    Playing,
+    /// Game ended, its results are shown while users vote for the next one
+    Results,
+    /// Host froze the room's timers, they continue from where they stopped once resumed
+    Paused,
+    /// Every game of the match was played, final wins are shown before a new match starts
+    MatchOver,
+}
+
+/// How long timed phases last and how many games a match has, set by the room's host
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+#[serde(default)]
+pub struct MatchSettings {
+    /// Milliseconds between a game being announced and it starting
+    pub countdown: u64,
+    /// Milliseconds results are shown, users vote for the next game meanwhile
+    pub results: u64,
+    /// Milliseconds final wins are shown before wins are reset and a new match starts
+    pub match_over: u64,
+    /// Games played in a match
+    pub games: usize,
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        Self {
+            countdown: 3_000,
+            results: 15_000,
+            match_over: 10_000,
+            games: 10,
+        }
+    }
+}
+
+impl MatchSettings {
+    /// Errors if a match would have no games or a phase would last longer than `MAX_PHASE`
+    pub fn validate(&self) -> Result<(), IuroError> {
+        if self.games == 0 {
+            return Err(IuroError::InvalidInput);
+        }
+        if [self.countdown, self.results, self.match_over]
+            .iter()
+            .any(|millis| *millis > MAX_PHASE)
+        {
+            return Err(IuroError::InvalidInput);
+        }
+        Ok(())
+    }
+
+    pub fn countdown(&self) -> Duration {
+        Duration::from_millis(self.countdown)
+    }
+
+    pub fn results(&self) -> Duration {
+        Duration::from_millis(self.results)
+    }
+
+    pub fn match_over(&self) -> Duration {
+        Duration::from_millis(self.match_over)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_phases_longer_than_max() {
+        let settings = MatchSettings {
+            countdown: 100_000_000_000,
+            ..MatchSettings::default()
+        };
+        assert!(matches!(settings.validate(), Err(IuroError::InvalidInput)));
+
+        let settings = MatchSettings {
+            results: MAX_PHASE + 1,
+            ..MatchSettings::default()
+        };
+        assert!(settings.validate().is_err());
+        let settings = MatchSettings {
+            match_over: MAX_PHASE + 1,
+            ..MatchSettings::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = MatchSettings {
+            countdown: MAX_PHASE,
+            results: MAX_PHASE,
+            match_over: MAX_PHASE,
+            games: 1,
+        };
+        assert!(settings.validate().is_ok());
+        assert!(MatchSettings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_match_without_games() {
+        let settings = MatchSettings {
+            games: 0,
+            ..MatchSettings::default()
+        };
+        assert!(matches!(settings.validate(), Err(IuroError::InvalidInput)));
+    }
+}
@@ -0,0 +1,165 @@
+//! Configures which games a room plays and how they are picked
+
+use crate::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::*};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Which games a room plays and how they are picked, set by the room's host
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Playlist {
+    /// Weight of each game, games missing or with weight 0 are excluded,
+    /// every game has the same weight if empty
+    pub weights: HashMap<GameKind, u32>,
+    /// Never picks the same game twice in a row, unless only one game is enabled
+    pub no_repeat: bool,
+    /// Fixed order of games, played in a loop, weights are ignored if not empty
+    pub order: Vec<GameKind>,
+}
+
+impl Playlist {
+    /// Errors if no game can be picked, or if weights add up to more than picking can handle
+    pub fn validate(&self) -> Result<(), IuroError> {
+        if self.order.is_empty()
+            && !self.weights.is_empty()
+            && self.weights.values().all(|weight| *weight == 0)
+        {
+            return Err(IuroError::EmptyPlaylist);
+        }
+        // `WeightedIndex` adds the weights up, overflowing would panic
+        self.weights
+            .values()
+            .try_fold(0u32, |total, weight| total.checked_add(*weight))
+            .ok_or(IuroError::PlaylistTooHeavy)?;
+        Ok(())
+    }
+
+    /// Picks next game, `position` is how many games were picked so far and `last` the latest one
+    pub fn pick<R: Rng + ?Sized>(
+        &self,
+        position: usize,
+        last: Option<GameKind>,
+        rng: &mut R,
+    ) -> GameKind {
+        if !self.order.is_empty() {
+            return self.order[position % self.order.len()];
+        }
+
+        let weight = |kind: &GameKind| {
+            if self.weights.is_empty() {
+                1
+            } else {
+                self.weights.get(kind).copied().unwrap_or(0)
+            }
+        };
+
+        let enabled: Vec<GameKind> = GameKind::ALL
+            .iter()
+            .copied()
+            .filter(|kind| weight(kind) > 0)
+            .collect();
+        let candidates: Vec<GameKind> = if self.no_repeat && enabled.len() > 1 {
+            enabled
+                .into_iter()
+                .filter(|kind| Some(*kind) != last)
+                .collect()
+        } else {
+            enabled
+        };
+
+        match WeightedIndex::new(candidates.iter().map(weight)) {
+            Ok(index) => candidates[index.sample(rng)],
+            // Playlists are validated, so this should never happen
+            Err(_) => GameKind::RockPapiuroScissor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    fn weighted(weights: &[(GameKind, u32)]) -> Playlist {
+        Playlist {
+            weights: weights.iter().copied().collect(),
+            ..Playlist::default()
+        }
+    }
+
+    #[test]
+    fn rejects_weights_that_overflow() {
+        let playlist = weighted(&[
+            (GameKind::MathSprint, u32::MAX),
+            (GameKind::TicTacToe, u32::MAX),
+        ]);
+        assert!(matches!(
+            playlist.validate(),
+            Err(IuroError::PlaylistTooHeavy)
+        ));
+
+        let playlist = weighted(&[(GameKind::MathSprint, u32::MAX), (GameKind::TicTacToe, 0)]);
+        assert!(playlist.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_playlist_without_games() {
+        let playlist = weighted(&[(GameKind::MathSprint, 0)]);
+        assert!(matches!(playlist.validate(), Err(IuroError::EmptyPlaylist)));
+        assert!(Playlist::default().validate().is_ok());
+    }
+
+    #[test]
+    fn picks_only_weighted_games() {
+        let playlist = weighted(&[(GameKind::MathSprint, 3), (GameKind::TicTacToe, 1)]);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut picks = HashMap::new();
+        for position in 0..1000 {
+            *picks
+                .entry(playlist.pick(position, None, &mut rng))
+                .or_insert(0) += 1;
+        }
+
+        assert_eq!(picks.len(), 2);
+        assert!(picks[&GameKind::MathSprint] > picks[&GameKind::TicTacToe] * 2);
+    }
+
+    #[test]
+    fn no_repeat_never_picks_last_game() {
+        let playlist = Playlist {
+            no_repeat: true,
+            ..weighted(&[(GameKind::MathSprint, 100), (GameKind::TicTacToe, 1)])
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut last = None;
+        for position in 0..100 {
+            let kind = playlist.pick(position, last, &mut rng);
+            assert_ne!(Some(kind), last);
+            last = Some(kind);
+        }
+    }
+
+    #[test]
+    fn order_loops_and_ignores_weights() {
+        let playlist = Playlist {
+            order: vec![GameKind::IuroSays, GameKind::ButtonMash],
+            ..weighted(&[(GameKind::MathSprint, 1)])
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let picks: Vec<GameKind> = (0..4)
+            .map(|position| playlist.pick(position, None, &mut rng))
+            .collect();
+
+        assert_eq!(
+            picks,
+            [
+                GameKind::IuroSays,
+                GameKind::ButtonMash,
+                GameKind::IuroSays,
+                GameKind::ButtonMash
+            ]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Wire format of `Command` and `Response`
+//!
+//! Version 0 is serde's externally tagged encoding, `{ "Join": "room" }` or `"ListRooms"`.
+//! Version 1 wraps the same data in an envelope, `{ "v": 1, "type": "join", "data": "room" }`.
+//!
+//! Clients choose their version with a `hello` handshake, sessions that never send it speak
+//! version 0. Version 0 commands are always accepted, so clients can migrate gradually.
+//!
+//! Either version may be encoded as JSON text frames, the default, or as MessagePack binary
+//! frames, chosen when connecting with the `iuro.msgpack` subprotocol or `?encoding=msgpack`.
+
+use crate::prelude::{Response, *};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, from_value, to_string, to_value, Map, Value};
+use std::convert::TryFrom;
+
+/// Newest version the server speaks
+pub const LATEST: u32 = 1;
+/// Subprotocol clients ask for to use MessagePack
+pub const MSGPACK_PROTOCOL: &str = "iuro.msgpack";
+
+/// How messages are serialized, chosen when connecting
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Encoding {
+    /// Text frames
+    Json,
+    /// Binary frames, smaller than JSON for high frequency games
+    MessagePack,
+}
+
+/// Serialized response, ready to be sent by the client's transport
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// Versioned message, `data` is absent for variants without data
+#[derive(Serialize, Deserialize, Debug)]
+struct Envelope {
+    v: u32,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    data: Value,
+}
+
+/// Message sent from client, in any version
+#[derive(Debug)]
+pub enum Incoming {
+    /// Client asked to speak a version '{ "v": 1, "type": "hello", "data": { "version": 1 } }'
+    Hello(u32),
+    Command(Command),
+}
+
+/// Version the server speaks with a client that asked for `requested`, newer ones are downgraded
+pub fn negotiate(requested: u32) -> u32 {
+    requested.min(LATEST)
+}
+
+/// Parses client's JSON message, with or without envelope
+pub fn decode(text: &str) -> Result<Incoming, IuroError> {
+    decode_value(from_str(text)?)
+}
+
+/// Parses client's MessagePack message, with or without envelope
+pub fn decode_binary(raw: &[u8]) -> Result<Incoming, IuroError> {
+    decode_value(rmp_serde::from_slice(raw)?)
+}
+
+fn decode_value(value: Value) -> Result<Incoming, IuroError> {
+    // Only envelopes have a version
+    if value.get("v").is_none() {
+        return Ok(Incoming::Command(from_value(value)?));
+    }
+
+    let envelope: Envelope = from_value(value)?;
+    // Every version can say hello, it's how versions are negotiated
+    if envelope.kind == "hello" {
+        let requested = match envelope.data.get("version").and_then(Value::as_u64) {
+            Some(version) => {
+                u32::try_from(version).map_err(|_| IuroError::UnsupportedVersion(version))?
+            }
+            None => envelope.v,
+        };
+        return Ok(Incoming::Hello(requested));
+    }
+    if envelope.v != LATEST {
+        return Err(IuroError::UnsupportedVersion(envelope.v.into()));
+    }
+
+    let tag = pascal_case(&envelope.kind);
+    let value = if envelope.data.is_null() {
+        Value::String(tag)
+    } else {
+        let mut map = Map::new();
+        map.insert(tag, envelope.data);
+        Value::Object(map)
+    };
+    Ok(Incoming::Command(from_value(value)?))
+}
+
+/// Serializes response in the session's version and encoding
+pub fn encode(response: &Response, version: u32, encoding: Encoding) -> Result<Frame, IuroError> {
+    let frame = match (version, encoding) {
+        (0, Encoding::Json) => Frame::Text(to_string(response)?),
+        (0, Encoding::MessagePack) => Frame::Binary(rmp_serde::to_vec_named(response)?),
+        (_, Encoding::Json) => Frame::Text(to_string(&envelope(response, version)?)?),
+        (_, Encoding::MessagePack) => {
+            Frame::Binary(rmp_serde::to_vec_named(&envelope(response, version)?)?)
+        }
+    };
+    Ok(frame)
+}
+
+/// Wraps response in the envelope of `version`
+fn envelope(response: &Response, version: u32) -> Result<Envelope, IuroError> {
+    let (tag, data) = match to_value(response)? {
+        Value::String(tag) => (tag, Value::Null),
+        Value::Object(map) => map
+            .into_iter()
+            .next()
+            // Externally tagged variants always have their tag
+            .unwrap_or_else(|| (String::new(), Value::Null)),
+        // This should never happen
+        other => (String::new(), other),
+    };
+
+    Ok(Envelope {
+        v: version,
+        kind: snake_case(&tag),
+        data,
+    })
+}
+
+/// `TeamMessage` becomes `team_message`
+fn snake_case(tag: &str) -> String {
+    let mut name = String::with_capacity(tag.len() + 4);
+    for (i, c) in tag.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.extend(c.to_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// `team_message` becomes `TeamMessage`
+fn pascal_case(kind: &str) -> String {
+    kind.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_version_0_commands() {
+        let incoming = decode(r#"{ "Join": "room" }"#).expect("valid v0 command");
+        assert!(matches!(incoming, Incoming::Command(Command::Join(room)) if room == "room"));
+        let incoming = decode(r#""ListRooms""#).expect("valid v0 command");
+        assert!(matches!(incoming, Incoming::Command(Command::ListRooms)));
+    }
+
+    #[test]
+    fn decodes_version_1_envelopes() {
+        let incoming = decode(r#"{ "v": 1, "type": "join", "data": "room" }"#).expect("valid v1");
+        assert!(matches!(incoming, Incoming::Command(Command::Join(room)) if room == "room"));
+        let incoming = decode(r#"{ "v": 1, "type": "list_rooms" }"#).expect("valid v1");
+        assert!(matches!(incoming, Incoming::Command(Command::ListRooms)));
+
+        let unknown = decode(r#"{ "v": 2, "type": "list_rooms" }"#);
+        assert!(matches!(unknown, Err(IuroError::UnsupportedVersion(2))));
+    }
+
+    #[test]
+    fn hello_negotiates_version() {
+        let incoming = decode(r#"{ "v": 1, "type": "hello", "data": { "version": 7 } }"#);
+        assert!(matches!(incoming, Ok(Incoming::Hello(7))));
+        assert_eq!(negotiate(7), LATEST);
+        assert_eq!(negotiate(0), 0);
+
+        // Envelope's version is requested when data doesn't have one
+        let incoming = decode(r#"{ "v": 0, "type": "hello" }"#);
+        assert!(matches!(incoming, Ok(Incoming::Hello(0))));
+    }
+
+    #[test]
+    fn hello_rejects_versions_that_overflow() {
+        let version = u64::from(u32::MAX) + 1;
+        let hello = format!(
+            r#"{{ "v": 1, "type": "hello", "data": {{ "version": {} }} }}"#,
+            version
+        );
+        assert!(matches!(
+            decode(&hello),
+            Err(IuroError::UnsupportedVersion(v)) if v == version
+        ));
+    }
+
+    #[test]
+    fn encodes_responses_in_session_version() {
+        let response = Response::Welcome(1);
+        let text = |frame| match frame {
+            Frame::Text(text) => text,
+            Frame::Binary(_) => panic!("JSON must be sent as text"),
+        };
+
+        let v0 = encode(&response, 0, Encoding::Json).expect("serializable response");
+        assert_eq!(text(v0), r#"{"Welcome":1}"#);
+        let v1 = encode(&response, 1, Encoding::Json).expect("serializable response");
+        assert_eq!(text(v1), r#"{"v":1,"type":"welcome","data":1}"#);
+    }
+}
@@ -1,16 +1,58 @@
 use actix::prelude::*;
-use rand::{distributions::Standard, prelude::*};
-use std::collections::HashMap;
+use rand::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
 
 use crate::{games, prelude::*};
 
 const EXPECTED_USERS: usize = 4;
+/// Last timer given to a phase, shared by every room so a room recreated with the same name
+/// never reuses the timers of the deleted one
+static TIMERS: AtomicUsize = AtomicUsize::new(0);
+/// How many games are kept queued
+const QUEUE_SIZE: usize = 10;
+/// How many queued games are shown to users
+const PREVIEW_SIZE: usize = 3;
+/// How many teams a room is split in, when in team mode
+const TEAMS: usize = 2;
+/// How many queued games users vote between when a game ends
+const CANDIDATES: usize = 3;
+/// Smallest change of a round-trip time, in milliseconds, worth showing to the room
+const RTT_STEP: u64 = 20;
 
 /// User's data when inside of a room
 pub struct RoomSlot {
     pub recipient: Recipient<Broadcast>,
     pub name: String,
     pub wins: usize,
+    /// User's team, if room is in team mode
+    pub team: Option<usize>,
+    /// Round-trip time to user's client in milliseconds, as last shown to the room, if its
+    /// transport measures it
+    pub rtt: Option<u64>,
+    /// Bots were added by the host and have no client, see `Bot`
+    pub bot: bool,
+}
+
+impl RoomSlot {
+    /// Records user's round-trip time if it changed noticeably, a quarter or `RTT_STEP` at least,
+    /// returns if it did, small jitter isn't worth broadcasting the roster again
+    pub fn set_rtt(&mut self, rtt: u64) -> bool {
+        let noticeable = match self.rtt {
+            Some(last) => last.abs_diff(rtt) >= RTT_STEP.max(last / 4),
+            None => true,
+        };
+        if noticeable {
+            self.rtt = Some(rtt);
+        }
+        noticeable
+    }
 }
 
 /// Manages room's users and its games
@@ -18,55 +60,265 @@ pub struct Room {
     sessions: HashMap<usize, RoomSlot>,
     games: Vec<Game>,
     game: Option<GameState>,
+    /// Incremented every time a game starts
+    round: usize,
+    phase: Phase,
+    /// When the current phase ends, `None` if it isn't timed
+    deadline: Option<Instant>,
+    /// Changes every time the phase changes, so stale timeouts can be ignored
+    timer: usize,
+    /// Phase to go back to when resumed, how long it had left and when it was paused, only while
+    /// paused
+    paused: Option<(Phase, Option<Duration>, Instant)>,
+    settings: MatchSettings,
+    /// Games started in the current match
+    played: usize,
+    /// Source of randomness for the room's games
+    rng: StdRng,
+    /// If users are split in teams
+    teams: bool,
+    /// Room's owner, the first user to join, replaced by another user if they leave
+    host: Option<usize>,
+    playlist: Playlist,
+    /// How many games were picked from the playlist since it was set
+    picked: usize,
+    /// Users that voted to skip the current game
+    skip_votes: HashSet<usize>,
+    /// Which candidate each user voted for, only between games
+    next_votes: Option<HashMap<usize, usize>>,
+    /// Users' wins when the current game started, to know who won it
+    starting_wins: HashMap<usize, usize>,
 }
 
 impl Default for Room {
     fn default() -> Self {
-        Self {
+        let mut room = Self {
             sessions: HashMap::default(),
             game: None,
-            games: (0..10).map(|_| random()).collect(),
-        }
+            games: Vec::with_capacity(QUEUE_SIZE),
+            round: 0,
+            phase: Phase::Lobby,
+            deadline: None,
+            timer: 0,
+            paused: None,
+            settings: MatchSettings::default(),
+            played: 0,
+            rng: StdRng::from_entropy(),
+            teams: false,
+            host: None,
+            playlist: Playlist::default(),
+            picked: 0,
+            skip_votes: HashSet::default(),
+            next_votes: None,
+            starting_wins: HashMap::default(),
+        };
+        room.refill_queue();
+        room
     }
 }
 
 impl Room {
-    /// Inserts user in room if there is space, broadcasts next game if join triggered it
-    pub fn join(&mut self, id: usize, slot: RoomSlot) -> JoinResult {
+    /// Inserts user in room if there is space, the match starts once the room is full
+    pub fn join(&mut self, id: usize, mut slot: RoomSlot) -> JoinResult {
         if self.sessions.len() == EXPECTED_USERS {
             return JoinResult::Full;
         }
 
+        // Teams from other rooms don't matter here
+        slot.team = None;
         self.sessions.insert(id, slot);
+        self.host.get_or_insert(id);
+        if self.teams {
+            self.balance_teams();
+        }
 
         if self.sessions.len() == EXPECTED_USERS {
-            JoinResult::NewGame(self.start_game())
+            JoinResult::Ready
         } else {
             debug!("User {} of {} joined", self.sessions.len(), EXPECTED_USERS);
             JoinResult::NoGame
         }
     }
 
+    /// Adds a bot to fill a seat, only the host can do it, `bot` creates its slot only once allowed
+    pub fn add_bot(
+        &mut self,
+        user_id: usize,
+        id: usize,
+        bot: impl FnOnce() -> RoomSlot,
+    ) -> Result<JoinResult, IuroError> {
+        if self.host != Some(user_id) {
+            return Err(IuroError::NotHost);
+        }
+        if self.sessions.len() == EXPECTED_USERS {
+            return Ok(JoinResult::Full);
+        }
+        Ok(self.join(id, bot()))
+    }
+
+    /// Removes bot named `name`, only the host can do it
+    pub fn remove_bot(&mut self, user_id: usize, name: &str) -> Result<RoomSlot, IuroError> {
+        if self.host != Some(user_id) {
+            return Err(IuroError::NotHost);
+        }
+
+        let id = self
+            .sessions
+            .iter()
+            .find(|(_, slot)| slot.bot && slot.name == name)
+            .map(|(id, _)| *id)
+            .ok_or_else(|| IuroError::NoBot(name.to_owned()))?;
+        self.remove_session(id)
+            .ok_or_else(|| IuroError::NoBot(name.to_owned()))
+    }
+
+    /// If any user that isn't a bot is in the room
+    pub fn has_users(&self) -> bool {
+        self.sessions.values().any(|slot| !slot.bot)
+    }
+
+    /// How many users aren't bots, votes never wait for bots
+    fn humans(&self) -> usize {
+        self.sessions.values().filter(|slot| !slot.bot).count()
+    }
+
+    /// If any user that isn't a bot can still play the current game, on boards they must be
+    /// seated in a match that isn't over
+    fn humans_can_play(&self) -> bool {
+        let is_human = |id: &usize| self.sessions.get(id).is_some_and(|slot| !slot.bot);
+        match &self.game {
+            Some(GameState::TicTacToe(state)) | Some(GameState::ConnectFour(state)) => state
+                .matches
+                .iter()
+                .filter(|m| m.result.is_none())
+                .flat_map(|m| m.sides.iter().flatten())
+                .any(is_human),
+            Some(_) => self.has_users(),
+            None => false,
+        }
+    }
+
     /// Instantiates next game in queue
     pub fn start_game(&mut self) -> Game {
-        let game = self.games.remove(0);
-        self.games.push(random());
+        let mut game = self.games.remove(0);
+        self.queue_game();
+        self.round += 1;
+        self.played += 1;
+        self.skip_votes.clear();
+        self.next_votes = None;
+        self.starting_wins = self
+            .sessions
+            .iter()
+            .map(|(id, slot)| (*id, slot.wins))
+            .collect();
+
+        if self.teams {
+            self.balance_teams();
+        }
 
         self.game = Some(match game.clone() {
             Game::RockPapiuroScissor => GameState::RockPapiuroScissor(HashMap::default()),
-            Game::TheRightIuro(state) => GameState::TheRightIuro((state, Default::default()))
+            Game::TheRightIuro(state) => GameState::TheRightIuro((state, Default::default())),
+            Game::MathSprint(problems) => GameState::MathSprint(MathSprintState::new(problems)),
+            Game::IuroSays((pool, sequence)) => GameState::IuroSays(IuroSaysState {
+                pool,
+                sequence,
+                players: self.sessions.keys().copied().collect(),
+                answers: HashMap::default(),
+            }),
+            Game::WordScramble(word) => {
+                // Users must only see the scrambled letters
+                game = Game::WordScramble(games::word_scramble::scramble(&word, &mut self.rng));
+                GameState::WordScramble(WordScrambleState {
+                    word,
+                    guesses: HashMap::default(),
+                })
+            }
+            Game::Majority(ref prompt) => GameState::Majority(MajorityState {
+                options: prompt.options.len(),
+                minority_wins: self.rng.gen(),
+                votes: HashMap::default(),
+            }),
+            Game::PrisonersDilemma(_) => {
+                let ids = self.sessions.keys().copied().collect();
+                let pairs = games::prisoners_dilemma::pair(ids, self.round);
+                let iterations = self.rng.gen_range(
+                    games::prisoners_dilemma::MIN_ITERATIONS,
+                    games::prisoners_dilemma::MAX_ITERATIONS + 1,
+                );
+                let state = DilemmaState::new(pairs, iterations);
+
+                // Users must know who they are playing against
+                game =
+                    Game::PrisonersDilemma(games::prisoners_dilemma::pairs(&state, &self.sessions));
+                GameState::PrisonersDilemma(state)
+            }
+            Game::TypingRace(ref passage) => GameState::TypingRace(TypingRaceState::new(passage)),
+            Game::ClosestGuess(ref estimate) => GameState::ClosestGuess(ClosestGuessState {
+                answer: estimate.answer(),
+                guesses: HashMap::default(),
+                submissions: 0,
+            }),
+            Game::TicTacToe(_) => {
+                let state = BoardState::new(BoardKind::TicTacToe, self.board_sides());
+                game = Game::TicTacToe(games::board::sides(&state, &self.sessions));
+                GameState::TicTacToe(state)
+            }
+            Game::ConnectFour(_) => {
+                let state = BoardState::new(BoardKind::ConnectFour, self.board_sides());
+                game = Game::ConnectFour(games::board::sides(&state, &self.sessions));
+                GameState::ConnectFour(state)
+            }
+            Game::ButtonMash(_) => GameState::ButtonMash(ButtonMashState::new()),
         });
 
         game
     }
 
+    /// Teams play against each other in a single board, otherwise users are paired
+    fn board_sides(&self) -> Vec<[Vec<usize>; 2]> {
+        let ids: Vec<usize> = self.sessions.keys().copied().collect();
+        if !self.teams {
+            return games::prisoners_dilemma::pair(ids, self.round)
+                .into_iter()
+                .map(|(a, b)| [vec![a], vec![b]])
+                .collect();
+        }
+
+        let mut sides = [Vec::new(), Vec::new()];
+        for id in ids {
+            if let Some(team) = self.sessions[&id].team.filter(|team| *team < 2) {
+                sides[team].push(id);
+            }
+        }
+        // Keeps turn order deterministic
+        sides[0].sort_unstable();
+        sides[1].sort_unstable();
+        vec![sides]
+    }
+
     /// Updates game state with user's input
-    pub fn update(
-        &mut self,
-        user_id: usize,
-        input: GameInput,
-    ) -> Result<(&'static str, HashMap<String, usize>), IuroError> {
-        let name;
+    pub fn update(&mut self, user_id: usize, input: GameInput) -> Result<UpdateResult, IuroError> {
+        let name = if let Some(game) = &self.game {
+            game.name()
+        } else {
+            warn!("User sent game input when it wasn't possible");
+            return Err(IuroError::NoGameRunning);
+        };
+        if self.phase == Phase::Paused {
+            return Err(IuroError::Paused);
+        }
+
+        let locked_in = self
+            .game
+            .as_ref()
+            .filter(|game| !game.answers_can_change())
+            .and_then(GameState::locked_in)
+            .is_some_and(|ids| ids.contains(&user_id));
+        if locked_in {
+            return Err(IuroError::LockedIn);
+        }
+
         let has_ended = match (self.game.as_mut(), &input) {
             (Some(GameState::RockPapiuroScissor(state)), GameInput::RockPapiuroScissor(input)) => {
                 let update = games::rock_papiuro_scissor::Update {
@@ -74,7 +326,6 @@ impl Room {
                     input: *input,
                     state,
                 };
-                name = "RockPapiuroScissor";
                 update.consume(&mut self.sessions)?
             }
             (Some(GameState::TheRightIuro(state)), GameInput::TheRightIuro(input)) => {
@@ -83,12 +334,131 @@ impl Room {
                     input: input.clone(),
                     state,
                 };
-                name = "TheRightIuro";
                 update.consume(&mut self.sessions)?
             }
-            (None, _) => {
-                warn!("User sent game input when it wasn't possible");
-                return Err(IuroError::NoGameRunning);
+            (Some(GameState::MathSprint(state)), GameInput::MathSprint(input)) => {
+                let update = games::math_sprint::Update {
+                    user_id,
+                    input: *input,
+                    state,
+                };
+                if !update.consume(&mut self.sessions)? {
+                    let progress = games::math_sprint::progress(state, &self.sessions);
+                    return Ok(UpdateResult::Progress(self.progress(name, progress)));
+                }
+                true
+            }
+            (Some(GameState::IuroSays(state)), GameInput::IuroSays(input)) => {
+                let update = games::iuro_says::Update {
+                    user_id,
+                    input: input.clone(),
+                    state,
+                    rng: &mut self.rng,
+                };
+                match update.consume(&mut self.sessions)? {
+                    games::iuro_says::Outcome::Waiting => false,
+                    games::iuro_says::Outcome::Ended => true,
+                    games::iuro_says::Outcome::NextRound => {
+                        let (sequence, players) = games::iuro_says::reveal(state, &self.sessions);
+                        return Ok(UpdateResult::NextRound(sequence, players));
+                    }
+                }
+            }
+            (Some(GameState::WordScramble(state)), GameInput::WordScramble(input)) => {
+                let update = games::word_scramble::Update {
+                    user_id,
+                    input: input.clone(),
+                    state,
+                };
+                update.consume(&mut self.sessions)?
+            }
+            (Some(GameState::Majority(state)), GameInput::Majority(input)) => {
+                let update = games::majority::Update {
+                    user_id,
+                    input: *input,
+                    state,
+                };
+                if let Some(split) = update.consume(&mut self.sessions)? {
+                    let results = GameResults::Majority(split);
+                    return Ok(UpdateResult::Ended(name, self.wins(), Some(results)));
+                }
+                false
+            }
+            (Some(GameState::PrisonersDilemma(state)), GameInput::PrisonersDilemma(input)) => {
+                let update = games::prisoners_dilemma::Update {
+                    user_id,
+                    input: *input,
+                    state,
+                };
+                match update.consume(&mut self.sessions)? {
+                    games::prisoners_dilemma::Outcome::Ended => true,
+                    games::prisoners_dilemma::Outcome::Waiting
+                    | games::prisoners_dilemma::Outcome::Progress => {
+                        let points = games::prisoners_dilemma::progress(state, &self.sessions);
+                        return Ok(UpdateResult::Progress(self.progress(name, points)));
+                    }
+                }
+            }
+            (Some(GameState::TypingRace(state)), GameInput::TypingRace(input)) => {
+                let update = games::typing_race::Update {
+                    user_id,
+                    input: input.clone(),
+                    state,
+                };
+                if update.consume(&mut self.sessions)? {
+                    let results = games::typing_race::results(state, &self.sessions);
+                    let results = Some(GameResults::TypingRace(results));
+                    return Ok(UpdateResult::Ended(name, self.wins(), results));
+                }
+
+                // Live positions of every user
+                let progress = games::typing_race::progress(state, &self.sessions);
+                return Ok(UpdateResult::Progress(self.progress(name, progress)));
+            }
+            (Some(GameState::ClosestGuess(state)), GameInput::ClosestGuess(input)) => {
+                let update = games::closest_guess::Update {
+                    user_id,
+                    input: *input,
+                    state,
+                };
+                if update.consume(&mut self.sessions)? {
+                    let results = games::closest_guess::results(state, &self.sessions);
+                    let results = Some(GameResults::ClosestGuess(results));
+                    return Ok(UpdateResult::Ended(name, self.wins(), results));
+                }
+                false
+            }
+            (Some(GameState::TicTacToe(state)), GameInput::TicTacToe(input))
+            | (Some(GameState::ConnectFour(state)), GameInput::ConnectFour(input)) => {
+                let update = games::board::Update {
+                    user_id,
+                    input: *input,
+                    state,
+                };
+                match update.consume(&mut self.sessions)? {
+                    games::board::Outcome::Moved(board) => {
+                        let view = games::board::view(state, board, &self.sessions);
+                        return Ok(UpdateResult::Moved(view));
+                    }
+                    games::board::Outcome::Ended => {
+                        let boards = games::board::views(state, &self.sessions);
+                        let results = match state.kind {
+                            BoardKind::TicTacToe => GameResults::TicTacToe(boards),
+                            BoardKind::ConnectFour => GameResults::ConnectFour(boards),
+                        };
+                        return Ok(UpdateResult::Ended(name, self.wins(), Some(results)));
+                    }
+                }
+            }
+            (Some(GameState::ButtonMash(state)), GameInput::ButtonMash(input)) => {
+                let update = games::button_mash::Update {
+                    user_id,
+                    input: *input,
+                    state,
+                };
+                // Only ends when time is up
+                update.consume()?;
+                false
             }
             _ => {
                 warn!("User sent game input when it wasn't possible");
@@ -97,18 +467,439 @@ impl Room {
         };
 
         if has_ended {
-            // If game has ended
-            Ok((name, self
-                .sessions
-                .values()
-                .map(|slot| (slot.name.clone(), slot.wins))
-                .collect()))
+            Ok(UpdateResult::Ended(name, self.wins(), None))
+        } else if self.game.as_ref().and_then(GameState::locked_in).is_some() {
+            // Users must know who the round is waiting on
+            Ok(UpdateResult::Progress(
+                self.progress(name, HashMap::default()),
+            ))
         } else {
             // Nobody won yet
-            Ok((name, HashMap::default()))
+            Ok(UpdateResult::Waiting)
+        }
+    }
+
+    /// Every user's input and result in the current game, `None` if no game is running
+    pub fn reveal(&self) -> Option<Reveal> {
+        let state = self.game.as_ref()?;
+        Some(games::reveal::reveal(
+            state,
+            &self.sessions,
+            &self.starting_wins,
+        ))
+    }
+
+    /// Game specific progress along with users that already answered the current round
+    fn progress(&self, game: &'static str, progress: HashMap<String, usize>) -> GameProgress {
+        let ids = self
+            .game
+            .as_ref()
+            .and_then(GameState::locked_in)
+            .unwrap_or_default();
+        let mut locked_in: Vec<String> = ids
+            .iter()
+            .filter_map(|id| self.sessions.get(id))
+            .map(|slot| slot.name.clone())
+            .collect();
+        // Keeps order stable between broadcasts
+        locked_in.sort();
+
+        GameProgress {
+            game: Cow::Borrowed(game),
+            progress,
+            locked_in,
+        }
+    }
+
+    /// Ends game since its time limit was reached
+    pub fn timeout(&mut self) -> Result<UpdateResult, IuroError> {
+        let (name, results) = match self.game.as_ref() {
+            Some(GameState::MathSprint(state)) => {
+                games::math_sprint::timeout(state, &mut self.sessions)?;
+                ("MathSprint", None)
+            }
+            // Nobody guessed the word
+            Some(GameState::WordScramble(_)) => ("WordScramble", None),
+            Some(GameState::TypingRace(state)) => {
+                games::typing_race::timeout(state, &mut self.sessions)?;
+                let results = games::typing_race::results(state, &self.sessions);
+                ("TypingRace", Some(GameResults::TypingRace(results)))
+            }
+            Some(GameState::ClosestGuess(state)) => {
+                games::closest_guess::timeout(state, &mut self.sessions)?;
+                let results = games::closest_guess::results(state, &self.sessions);
+                ("ClosestGuess", Some(GameResults::ClosestGuess(results)))
+            }
+            Some(GameState::ButtonMash(state)) => {
+                games::button_mash::timeout(state, &mut self.sessions)?;
+                let results = games::button_mash::results(state, &self.sessions);
+                ("ButtonMash", Some(GameResults::ButtonMash(results)))
+            }
+            // Games without time limit, or game was reset
+            Some(GameState::RockPapiuroScissor(_))
+            | Some(GameState::TheRightIuro(_))
+            | Some(GameState::IuroSays(_))
+            | Some(GameState::Majority(_))
+            | Some(GameState::PrisonersDilemma(_))
+            | Some(GameState::TicTacToe(_))
+            | Some(GameState::ConnectFour(_))
+            | None => return Ok(UpdateResult::Waiting),
+        };
+
+        Ok(UpdateResult::Ended(name, self.wins(), results))
+    }
+
+    /// Registers user's vote to skip current game, it ends without winners once most users that
+    /// aren't bots voted
+    ///
+    /// Bots' votes only skip the game if no user that isn't a bot can play it anymore
+    pub fn vote_skip(&mut self, user_id: usize) -> Result<SkipResult, IuroError> {
+        let name = self.game.as_ref().ok_or(IuroError::NoGameRunning)?.name();
+        if self.phase == Phase::Paused {
+            return Err(IuroError::Paused);
+        }
+
+        self.skip_votes.insert(user_id);
+        let votes = self
+            .skip_votes
+            .iter()
+            .filter(|id| self.sessions.get(id).is_some_and(|slot| !slot.bot))
+            .count();
+        let needed = self.humans() / 2 + 1;
+        if votes < needed && self.humans_can_play() {
+            return Ok(SkipResult::Votes(votes, needed));
+        }
+        Ok(SkipResult::Skipped(UpdateResult::Ended(
+            name,
+            self.wins(),
+            None,
+        )))
+    }
+
+    /// Stops current game, keeping the users' wins
+    pub fn end_game(&mut self) {
+        self.game = None;
+        self.skip_votes.clear();
+    }
+
+    /// If every game of the match was played
+    pub fn match_over(&self) -> bool {
+        self.played >= self.settings.games
+    }
+
+    /// Resets wins and starts counting games again
+    pub fn new_match(&mut self) {
+        self.played = 0;
+        for slot in self.sessions.values_mut() {
+            slot.wins = 0;
+        }
+    }
+
+    /// Stops current game and starts voting for the next one, returns the candidates
+    pub fn start_next_vote(&mut self) -> Vec<GameKind> {
+        self.end_game();
+        self.next_votes = Some(HashMap::default());
+        self.candidates().unwrap_or_default()
+    }
+
+    /// Games users may vote for, the first one is played if nobody votes, `None` if not voting
+    pub fn candidates(&self) -> Option<Vec<GameKind>> {
+        self.next_votes.as_ref()?;
+        Some(self.games.iter().take(CANDIDATES).map(Game::kind).collect())
+    }
+
+    /// Registers user's vote for the next game, returns votes of each candidate and if every user
+    /// that isn't a bot voted
+    pub fn vote_next(
+        &mut self,
+        user_id: usize,
+        candidate: usize,
+    ) -> Result<(Vec<usize>, bool), IuroError> {
+        if self.phase == Phase::Paused {
+            return Err(IuroError::Paused);
+        }
+        let candidates = self.games.len().min(CANDIDATES);
+        // Bots never vote for the next game
+        let humans = self.humans();
+        let votes = self.next_votes.as_mut().ok_or(IuroError::NoVote)?;
+        if candidate >= candidates {
+            return Err(IuroError::InvalidInput);
+        }
+
+        votes.insert(user_id, candidate);
+        let everybody = votes.len() >= humans;
+        Ok((tally(votes, candidates), everybody))
+    }
+
+    /// Ends vote for the next game, moving the most voted one to the front of the queue
+    ///
+    /// Ties go to the earliest queued candidate
+    pub fn finish_next_vote(&mut self) {
+        let votes = match self.next_votes.take() {
+            Some(votes) => votes,
+            None => return,
+        };
+
+        let tally = tally(&votes, self.games.len().min(CANDIDATES));
+        // Iterating backwards makes `max_by_key` return the earliest of the tied candidates
+        if let Some(chosen) = (0..tally.len()).rev().max_by_key(|i| tally[*i]) {
+            let game = self.games.remove(chosen);
+            self.games.insert(0, game);
         }
     }
 
+    /// Moves room to `phase`, returns the timer that must end it if it's timed
+    pub fn set_phase(&mut self, phase: Phase, duration: Option<Duration>) -> usize {
+        self.phase = phase;
+        self.deadline = duration.map(|duration| Instant::now() + duration);
+        self.timer = TIMERS.fetch_add(1, Ordering::Relaxed) + 1;
+        self.timer
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Identifies the current phase's timer
+    pub fn timer(&self) -> usize {
+        self.timer
+    }
+
+    /// Freezes room's timers, only the host can do it, the phase must then be set to `Paused`
+    pub fn pause(&mut self, user_id: usize) -> Result<(), IuroError> {
+        if self.host != Some(user_id) {
+            return Err(IuroError::NotHost);
+        }
+        match self.phase {
+            Phase::Countdown | Phase::Playing | Phase::Results | Phase::MatchOver => {}
+            Phase::Lobby | Phase::Paused => return Err(IuroError::CantPause),
+        }
+
+        let remaining = self
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        self.paused = Some((self.phase, remaining, Instant::now()));
+        Ok(())
+    }
+
+    /// Unfreezes room's timers, only the host can do it, returns phase to go back to and its time left
+    pub fn resume(&mut self, user_id: usize) -> Result<(Phase, Option<Duration>), IuroError> {
+        if self.host != Some(user_id) {
+            return Err(IuroError::NotHost);
+        }
+        let (phase, remaining, paused_at) = self.paused.take().ok_or(IuroError::NotPaused)?;
+        if let Some(game) = &mut self.game {
+            game.shift_clock(paused_at.elapsed());
+        }
+        Ok((phase, remaining))
+    }
+
+    /// Changes match settings, only the host can do it, takes effect in the next phase
+    pub fn set_settings(
+        &mut self,
+        user_id: usize,
+        settings: MatchSettings,
+    ) -> Result<(), IuroError> {
+        if self.host != Some(user_id) {
+            return Err(IuroError::NotHost);
+        }
+        settings.validate()?;
+
+        self.settings = settings;
+        Ok(())
+    }
+
+    pub fn settings(&self) -> &MatchSettings {
+        &self.settings
+    }
+
+    /// Picks a game from the playlist and queues it
+    fn queue_game(&mut self) {
+        let last = self.games.last().map(Game::kind);
+        let kind = self.playlist.pick(self.picked, last, &mut self.rng);
+        self.picked += 1;
+        let game = self.rng.sample(kind);
+        self.games.push(game);
+    }
+
+    /// Replaces queued games with new ones picked from the playlist
+    fn refill_queue(&mut self) {
+        self.games.clear();
+        self.picked = 0;
+        while self.games.len() < QUEUE_SIZE {
+            self.queue_game();
+        }
+    }
+
+    /// Changes which games are played, only the host can do it, takes effect after current game
+    pub fn set_playlist(&mut self, user_id: usize, playlist: Playlist) -> Result<(), IuroError> {
+        if self.host != Some(user_id) {
+            return Err(IuroError::NotHost);
+        }
+        playlist.validate()?;
+
+        self.playlist = playlist;
+        self.refill_queue();
+        // Candidates being voted for were replaced
+        if let Some(votes) = self.next_votes.as_mut() {
+            votes.clear();
+        }
+        Ok(())
+    }
+
+    /// Next games to be played
+    pub fn next_up(&self) -> Vec<GameKind> {
+        self.games
+            .iter()
+            .take(PREVIEW_SIZE)
+            .map(Game::kind)
+            .collect()
+    }
+
+    /// Splits users in teams, or stops doing so, only the host can do it and not while a game is running
+    pub fn set_team_mode(&mut self, user_id: usize, enabled: bool) -> Result<(), IuroError> {
+        if self.host != Some(user_id) {
+            return Err(IuroError::NotHost);
+        }
+        if self.game.is_some() {
+            return Err(IuroError::GameRunning);
+        }
+
+        self.teams = enabled;
+        for slot in self.sessions.values_mut() {
+            slot.team = None;
+        }
+        if enabled {
+            self.balance_teams();
+        }
+        Ok(())
+    }
+
+    /// Moves user to the chosen team, teams are balanced again when the game starts
+    pub fn choose_team(&mut self, user_id: usize, team: usize) -> Result<(), IuroError> {
+        if !self.teams {
+            return Err(IuroError::NoTeams);
+        }
+        if self.game.is_some() {
+            return Err(IuroError::GameRunning);
+        }
+        if team >= TEAMS {
+            return Err(IuroError::InvalidInput);
+        }
+
+        self.sessions
+            .get_mut(&user_id)
+            // This should never happen
+            .ok_or(IuroError::AddrNotFound(user_id))?
+            .team = Some(team);
+        Ok(())
+    }
+
+    /// Assigns users without a team to the smallest ones, then moves users until teams are even
+    fn balance_teams(&mut self) {
+        let mut ids: Vec<usize> = self.sessions.keys().copied().collect();
+        // Keeps balancing deterministic
+        ids.sort_unstable();
+
+        let mut members = vec![Vec::new(); TEAMS];
+        let mut unassigned = Vec::new();
+        for id in ids {
+            match self.sessions[&id].team {
+                Some(team) if team < TEAMS => members[team].push(id),
+                _ => unassigned.push(id),
+            }
+        }
+
+        let smallest = |members: &[Vec<usize>]| (0..TEAMS).min_by_key(|team| members[*team].len());
+        let largest = |members: &[Vec<usize>]| (0..TEAMS).max_by_key(|team| members[*team].len());
+
+        for id in unassigned {
+            if let Some(team) = smallest(&members) {
+                members[team].push(id);
+            }
+        }
+
+        while let (Some(small), Some(large)) = (smallest(&members), largest(&members)) {
+            if members[large].len() - members[small].len() <= 1 {
+                break;
+            }
+            if let Some(id) = members[large].pop() {
+                members[small].push(id);
+            }
+        }
+
+        for (team, ids) in members.iter().enumerate() {
+            for id in ids {
+                if let Some(slot) = self.sessions.get_mut(id) {
+                    slot.team = Some(team);
+                }
+            }
+        }
+    }
+
+    /// Users in the same team as `user_id`, including them
+    pub fn teammates(&self, user_id: usize) -> Result<Vec<&RoomSlot>, IuroError> {
+        let team = self
+            .sessions
+            .get(&user_id)
+            // This should never happen
+            .ok_or(IuroError::AddrNotFound(user_id))?
+            .team
+            .ok_or(IuroError::NoTeams)?;
+
+        Ok(self
+            .sessions
+            .values()
+            .filter(|slot| slot.team == Some(team))
+            .collect())
+    }
+
+    /// Members of each team and their summed wins, `None` if room isn't in team mode
+    pub fn standings(&self) -> Option<Vec<TeamStanding>> {
+        if !self.teams {
+            return None;
+        }
+
+        let mut standings: Vec<TeamStanding> = (0..TEAMS)
+            .map(|team| TeamStanding {
+                team,
+                members: Vec::new(),
+                wins: 0,
+            })
+            .collect();
+        for slot in self.sessions.values() {
+            if let Some(standing) = slot.team.and_then(|team| standings.get_mut(team)) {
+                standing.members.push(slot.name.clone());
+                standing.wins += slot.wins;
+            }
+        }
+        Some(standings)
+    }
+
+    /// Every user in the room, sorted by name
+    pub fn roster(&self) -> Vec<RosterEntry> {
+        let mut roster: Vec<RosterEntry> = self
+            .sessions
+            .values()
+            .map(|slot| RosterEntry {
+                name: slot.name.clone(),
+                team: slot.team,
+                rtt: slot.rtt,
+                bot: slot.bot,
+            })
+            .collect();
+        roster.sort_by(|a, b| a.name.cmp(&b.name));
+        roster
+    }
+
+    /// Map of users and their wins
+    fn wins(&self) -> HashMap<String, usize> {
+        self.sessions
+            .values()
+            .map(|slot| (slot.name.clone(), slot.wins))
+            .collect()
+    }
+
     pub fn sessions(&self) -> &HashMap<usize, RoomSlot> {
         &self.sessions
     }
@@ -118,45 +909,357 @@ impl Room {
     }
 
     pub fn remove_session(&mut self, id: usize) -> Option<RoomSlot> {
-        self.sessions.remove(&id)
+        let slot = self.sessions.remove(&id);
+        if self.host == Some(id) {
+            // Oldest user can't be known, so any user becomes the host, but never a bot
+            self.host = self
+                .sessions
+                .iter()
+                .filter(|(_, slot)| !slot.bot)
+                .map(|(id, _)| *id)
+                .min();
+        }
+        slot
     }
 
+    /// Stops current game and goes back to the lobby, the match continues once the room is full
     pub fn reset_game(&mut self) {
-        self.game = None;
+        self.end_game();
+        self.next_votes = None;
+        self.paused = None;
+        self.set_phase(Phase::Lobby, None);
     }
 }
 
-impl Distribution<Game> for Standard {
+/// Each kind of game is a distribution of games of that kind
+impl Distribution<Game> for GameKind {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Game {
-        let game = match rng.gen_range(0, 2) {
-            0 => Game::RockPapiuroScissor,
-            _ => {
-                let mut values = Vec::with_capacity(8);
-                while values.len() < 8 {
-                    let value = rng.gen_range(0, 36);
-                    if !values.contains(&value) {
-                        values.push(value);
-                    }
-                }
-                Game::TheRightIuro(values)
+        match self {
+            GameKind::RockPapiuroScissor => Game::RockPapiuroScissor,
+            GameKind::TheRightIuro => Game::TheRightIuro(distinct_values(rng, 8)),
+            GameKind::MathSprint => Game::MathSprint(
+                (0..games::math_sprint::PROBLEMS)
+                    .map(|_| rng.gen())
+                    .collect(),
+            ),
+            GameKind::IuroSays => {
+                let pool = distinct_values(rng, games::iuro_says::POOL_SIZE);
+                let sequence = (0..games::iuro_says::INITIAL_LENGTH)
+                    // Pool is never empty
+                    .map(|_| *pool.choose(rng).unwrap_or(&0))
+                    .collect();
+                Game::IuroSays((pool, sequence))
             }
-        };
+            GameKind::WordScramble => {
+                let dictionary = games::word_scramble::dictionary();
+                // Dictionary always has words to be scrambled
+                Game::WordScramble(dictionary.choose(rng).unwrap_or("iuro").to_owned())
+            }
+            GameKind::Majority => Game::Majority(games::majority::choose(rng)),
+            GameKind::PrisonersDilemma => Game::PrisonersDilemma(Vec::new()),
+            GameKind::TypingRace => Game::TypingRace(games::typing_race::choose(rng)),
+            GameKind::ClosestGuess => Game::ClosestGuess(rng.gen()),
+            GameKind::TicTacToe => Game::TicTacToe(Vec::new()),
+            GameKind::ConnectFour => Game::ConnectFour(Vec::new()),
+            GameKind::ButtonMash => Game::ButtonMash(games::button_mash::WINDOW.as_millis() as u64),
+        }
+    }
+}
 
-        // This is here so the code breaks whenever new variants are added,
-        // since the random generation above will only break silently at runtime
-        match game {
-            Game::RockPapiuroScissor => game,
-            game @ Game::TheRightIuro(_) => game,
+/// Generates `count` distinct values, each one identifies an iuro image
+fn distinct_values<R: Rng + ?Sized>(rng: &mut R, count: usize) -> Vec<u8> {
+    let mut values = Vec::with_capacity(count);
+    while values.len() < count {
+        let value = rng.gen_range(0, 36);
+        if !values.contains(&value) {
+            values.push(value);
         }
     }
+    values
+}
+
+/// Counts votes of each candidate
+fn tally(votes: &HashMap<usize, usize>, candidates: usize) -> Vec<usize> {
+    let mut tally = vec![0; candidates];
+    for candidate in votes.values() {
+        if let Some(count) = tally.get_mut(*candidate) {
+            *count += 1;
+        }
+    }
+    tally
+}
+
+/// Team's members and how many wins they have together
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct TeamStanding {
+    pub team: usize,
+    pub members: Vec<String>,
+    pub wins: usize,
+}
+
+/// User in a room and how responsive their connection is
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct RosterEntry {
+    pub name: String,
+    pub team: Option<usize>,
+    /// Last round-trip time in milliseconds, `None` if client's transport doesn't measure it
+    pub rtt: Option<u64>,
+    pub bot: bool,
 }
 
 /// Possible results of trying to join a room
 pub enum JoinResult {
-    /// User joining triggered game start
-    NewGame(Game),
-    /// User joined, but game is not ready to start yet
+    /// User joining filled the room, match may start
+    Ready,
+    /// User joined, but room isn't full yet
     NoGame,
     /// Can't join room since it's already full
     Full,
 }
+
+/// Possible results of voting to skip a game
+pub enum SkipResult {
+    /// Not enough votes yet, returns how many users voted and how many votes are needed
+    Votes(usize, usize),
+    /// Game was skipped, nobody won it
+    Skipped(UpdateResult),
+}
+
+/// Possible results of updating a room's game with user's input
+pub enum UpdateResult {
+    /// Game is waiting for more inputs
+    Waiting,
+    /// Game is still running, returns users' progress and who already answered
+    Progress(GameProgress),
+    /// User made a move, returns the board it changed
+    Moved(BoardView),
+    /// Game moved to its next round, returns what must be revealed and users still playing
+    NextRound(Vec<u8>, Vec<String>),
+    /// Game ended, returns map of users and their wins, and game specific results
+    Ended(&'static str, HashMap<String, usize>, Option<GameResults>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::slot;
+
+    /// Room with `users` users and `bots` bots, the first user is the host
+    fn room(users: usize, bots: usize) -> Room {
+        let mut room = Room::default();
+        for id in 0..users + bots {
+            room.join(id, slot(id, id >= users));
+        }
+        room
+    }
+
+    #[test]
+    fn bots_are_only_created_once_allowed() {
+        let _system = System::new("test");
+        let mut room = room(EXPECTED_USERS - 1, 0);
+        assert!(matches!(
+            room.add_bot(1, 10, || panic!("bot created without being allowed")),
+            Err(IuroError::NotHost)
+        ));
+
+        let added = room.add_bot(0, 10, || slot(10, true));
+        assert!(matches!(added, Ok(JoinResult::Ready)));
+        assert!(matches!(
+            room.add_bot(0, 11, || panic!("bot created without being allowed")),
+            Ok(JoinResult::Full)
+        ));
+        assert_eq!(room.sessions().len(), EXPECTED_USERS);
+    }
+
+    #[test]
+    fn only_noticeable_rtt_changes_are_recorded() {
+        let _system = System::new("test");
+        let mut room = room(1, 0);
+        let slot = room.sessions_mut().get_mut(&0).expect("user joined");
+
+        assert!(slot.set_rtt(100));
+        assert!(!slot.set_rtt(110));
+        assert!(!slot.set_rtt(90));
+        assert_eq!(slot.rtt, Some(100));
+        // Drift is measured from the last recorded time, so it can't creep unnoticed
+        assert!(slot.set_rtt(125));
+        assert_eq!(slot.rtt, Some(125));
+
+        assert!(slot.set_rtt(1000));
+        assert!(!slot.set_rtt(1200));
+        assert!(slot.set_rtt(1300));
+    }
+
+    #[test]
+    fn next_vote_ends_once_every_human_voted() {
+        let _system = System::new("test");
+        let mut room = room(2, 2);
+        assert!(matches!(room.vote_next(0, 0), Err(IuroError::NoVote)));
+
+        let candidates = room.start_next_vote();
+        assert_eq!(candidates.len(), CANDIDATES);
+        assert!(matches!(
+            room.vote_next(0, CANDIDATES),
+            Err(IuroError::InvalidInput)
+        ));
+        let (tally, everybody) = room.vote_next(0, 1).expect("voting for next game");
+        assert_eq!(tally, vec![0, 1, 0]);
+        assert!(!everybody);
+        let (tally, everybody) = room.vote_next(1, 1).expect("voting for next game");
+        assert_eq!(tally, vec![0, 2, 0]);
+        assert!(everybody);
+
+        room.finish_next_vote();
+        assert_eq!(room.start_game().kind(), candidates[1]);
+    }
+
+    #[test]
+    fn skip_needs_most_humans() {
+        let _system = System::new("test");
+        let mut room = room(3, 1);
+        assert!(matches!(room.vote_skip(0), Err(IuroError::NoGameRunning)));
+
+        room.start_game();
+        assert!(matches!(room.vote_skip(0), Ok(SkipResult::Votes(1, 2))));
+        // Voting twice changes nothing
+        assert!(matches!(room.vote_skip(0), Ok(SkipResult::Votes(1, 2))));
+        assert!(matches!(room.vote_skip(1), Ok(SkipResult::Skipped(_))));
+    }
+
+    #[test]
+    fn bots_only_skip_boards_without_humans() {
+        let _system = System::new("test");
+        let mut room = room(2, 2);
+        let board = |sides| {
+            Some(GameState::TicTacToe(BoardState::new(
+                BoardKind::TicTacToe,
+                sides,
+            )))
+        };
+
+        // Users 0 and 1 aren't bots, each one plays against a bot
+        room.game = board(vec![[vec![0], vec![2]], [vec![1], vec![3]]]);
+        assert!(matches!(room.vote_skip(2), Ok(SkipResult::Votes(0, 2))));
+        assert!(matches!(room.vote_skip(3), Ok(SkipResult::Votes(0, 2))));
+        assert!(matches!(room.vote_skip(0), Ok(SkipResult::Votes(1, 2))));
+        assert!(matches!(room.vote_skip(1), Ok(SkipResult::Skipped(_))));
+        room.end_game();
+
+        room.game = board(vec![[vec![0], vec![1]], [vec![2], vec![3]]]);
+        assert!(matches!(room.vote_skip(2), Ok(SkipResult::Votes(0, 2))));
+        room.end_game();
+
+        // Users that aren't bots are sitting out
+        room.game = board(vec![[vec![2], vec![3]]]);
+        assert!(matches!(room.vote_skip(2), Ok(SkipResult::Skipped(_))));
+    }
+
+    #[test]
+    fn recreated_room_never_reuses_timers() {
+        let mut deleted = Room::default();
+        let stale = deleted.set_phase(Phase::Countdown, Some(Duration::from_secs(3)));
+
+        let mut recreated = Room::default();
+        assert_ne!(recreated.timer(), stale);
+        let timer = recreated.set_phase(Phase::Countdown, Some(Duration::from_secs(3)));
+        assert_ne!(timer, stale);
+        assert_eq!(recreated.timer(), timer);
+    }
+
+    #[test]
+    fn every_phase_change_invalidates_timer() {
+        let _system = System::new("test");
+        let mut room = room(EXPECTED_USERS, 0);
+        let countdown = room.set_phase(Phase::Countdown, Some(Duration::from_secs(3)));
+        let playing = room.set_phase(Phase::Playing, Some(Duration::from_secs(30)));
+        assert!(playing > countdown);
+
+        room.reset_game();
+        assert_eq!(room.phase(), Phase::Lobby);
+        assert!(room.timer() > playing);
+    }
+
+    #[test]
+    fn match_ends_after_its_games() {
+        let _system = System::new("test");
+        let mut room = room(EXPECTED_USERS, 0);
+        let settings = |games| MatchSettings {
+            games,
+            ..MatchSettings::default()
+        };
+        assert!(matches!(
+            room.set_settings(1, settings(2)),
+            Err(IuroError::NotHost)
+        ));
+        assert!(matches!(
+            room.set_settings(0, settings(0)),
+            Err(IuroError::InvalidInput)
+        ));
+        room.set_settings(0, settings(2))
+            .expect("host changes settings");
+
+        room.start_game();
+        room.end_game();
+        assert!(!room.match_over());
+        room.start_game();
+        room.sessions_mut().get_mut(&1).expect("user joined").wins = 1;
+        room.end_game();
+        assert!(room.match_over());
+
+        room.new_match();
+        assert!(!room.match_over());
+        assert!(room.sessions().values().all(|slot| slot.wins == 0));
+    }
+
+    #[test]
+    fn only_host_pauses_timed_phases() {
+        let _system = System::new("test");
+        let mut room = room(EXPECTED_USERS, 0);
+        assert!(matches!(room.pause(0), Err(IuroError::CantPause)));
+
+        room.set_phase(Phase::Playing, Some(Duration::from_secs(30)));
+        assert!(matches!(room.pause(1), Err(IuroError::NotHost)));
+        assert!(matches!(room.resume(0), Err(IuroError::NotPaused)));
+        assert!(room.pause(0).is_ok());
+        room.set_phase(Phase::Paused, None);
+        assert!(matches!(room.pause(0), Err(IuroError::CantPause)));
+        assert!(matches!(
+            room.update(0, GameInput::ButtonMash(1)),
+            Err(IuroError::NoGameRunning)
+        ));
+
+        assert!(matches!(room.resume(1), Err(IuroError::NotHost)));
+        let (phase, remaining) = room.resume(0).expect("room is paused");
+        assert_eq!(phase, Phase::Playing);
+        assert!(remaining.is_some_and(|remaining| remaining <= Duration::from_secs(30)));
+        assert!(matches!(room.resume(0), Err(IuroError::NotPaused)));
+    }
+
+    #[test]
+    fn resuming_moves_game_clocks_forward() {
+        let _system = System::new("test");
+        let mut room = room(EXPECTED_USERS, 0);
+        let mash = ButtonMashState::new();
+        let started = mash.started;
+        room.game = Some(GameState::ButtonMash(mash));
+        room.set_phase(Phase::Playing, Some(Duration::from_secs(10)));
+
+        room.pause(0).expect("host pauses playing room");
+        room.set_phase(Phase::Paused, None);
+        assert!(matches!(
+            room.update(0, GameInput::ButtonMash(1)),
+            Err(IuroError::Paused)
+        ));
+        // Pretends the room was paused for a minute
+        let paused = Duration::from_secs(60);
+        if let Some((_, _, paused_at)) = &mut room.paused {
+            *paused_at -= paused;
+        }
+
+        room.resume(0).expect("room is paused");
+        match &room.game {
+            Some(GameState::ButtonMash(mash)) => assert!(mash.started >= started + paused),
+            _ => panic!("game changed while paused"),
+        }
+    }
+}
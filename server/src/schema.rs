@@ -0,0 +1,175 @@
+//! JSON Schema and TypeScript definitions of the protocol, generated from its types
+//!
+//! Checked-in files live in `server/schema`, regenerate them with `cargo run -- schema`.
+
+use crate::prelude::{Response, *};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject, SingleOrVec},
+    schema_for,
+};
+use serde_json::{to_string_pretty, Value};
+use std::{fs, io, path::Path};
+
+/// Name and contents of every generated file
+pub fn files() -> Vec<(&'static str, String)> {
+    let schemas = vec![
+        ("command.json", schema_for!(Command)),
+        ("response.json", schema_for!(Response)),
+        ("game_input.json", schema_for!(GameInput)),
+        ("game.json", schema_for!(Game)),
+    ];
+
+    let mut files: Vec<(&'static str, String)> = schemas
+        .into_iter()
+        .map(|(file, schema)| {
+            // Serializing a schema never fails
+            let json = to_string_pretty(&schema).unwrap_or_default();
+            (file, json + "\n")
+        })
+        .collect();
+    files.push(("protocol.d.ts", typescript()));
+    files
+}
+
+/// Writes every generated file to `dir`
+pub fn write(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for (file, contents) in files() {
+        fs::write(dir.join(file), contents)?;
+    }
+    Ok(())
+}
+
+/// TypeScript typings of the protocol types and every type they use
+fn typescript() -> String {
+    let mut gen = SchemaGenerator::default();
+    gen.subschema_for::<Command>();
+    gen.subschema_for::<Response>();
+    gen.subschema_for::<GameInput>();
+    gen.subschema_for::<Game>();
+
+    let mut ts =
+        String::from("// Generated from the server's protocol types, don't edit it by hand\n");
+    for (name, schema) in gen.definitions() {
+        ts.push('\n');
+        let description = match schema {
+            Schema::Object(object) => object
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.description.as_ref()),
+            Schema::Bool(_) => None,
+        };
+        if let Some(description) = description {
+            ts.push_str("/**\n");
+            for line in description.lines() {
+                ts.push_str(&format!(" * {}\n", line).replace(" * \n", " *\n"));
+            }
+            ts.push_str(" */\n");
+        }
+        ts.push_str(&format!("export type {} = {};\n", name, type_of(schema)));
+    }
+    ts
+}
+
+/// TypeScript type equivalent to `schema`
+fn type_of(schema: &Schema) -> String {
+    match schema {
+        Schema::Bool(true) => "unknown".to_owned(),
+        Schema::Bool(false) => "never".to_owned(),
+        Schema::Object(object) => object_type(object),
+    }
+}
+
+fn object_type(object: &SchemaObject) -> String {
+    if let Some(reference) = &object.reference {
+        // References always point to `#/definitions/<name>`
+        return reference.rsplit('/').next().unwrap_or("unknown").to_owned();
+    }
+
+    if let Some(subschemas) = &object.subschemas {
+        if let Some(schemas) = subschemas.one_of.as_ref().or(subschemas.any_of.as_ref()) {
+            return union(schemas.iter().map(type_of));
+        }
+        if let Some(schemas) = &subschemas.all_of {
+            return schemas.iter().map(type_of).collect::<Vec<_>>().join(" & ");
+        }
+    }
+
+    if let Some(values) = &object.enum_values {
+        return union(values.iter().map(literal));
+    }
+    if let Some(value) = &object.const_value {
+        return literal(value);
+    }
+
+    match &object.instance_type {
+        Some(SingleOrVec::Single(instance)) => instance_type(object, **instance),
+        Some(SingleOrVec::Vec(instances)) => union(
+            instances
+                .iter()
+                .map(|instance| instance_type(object, *instance)),
+        ),
+        None => "unknown".to_owned(),
+    }
+}
+
+fn instance_type(object: &SchemaObject, instance: InstanceType) -> String {
+    match instance {
+        InstanceType::Null => "null".to_owned(),
+        InstanceType::Boolean => "boolean".to_owned(),
+        InstanceType::Integer | InstanceType::Number => "number".to_owned(),
+        InstanceType::String => "string".to_owned(),
+        InstanceType::Array => match object.array.as_ref().and_then(|a| a.items.as_ref()) {
+            Some(SingleOrVec::Single(item)) => {
+                let item = type_of(item);
+                // Unions must be grouped before becoming arrays
+                if item.contains(' ') {
+                    format!("({})[]", item)
+                } else {
+                    format!("{}[]", item)
+                }
+            }
+            Some(SingleOrVec::Vec(items)) => {
+                let items: Vec<String> = items.iter().map(type_of).collect();
+                format!("[{}]", items.join(", "))
+            }
+            None => "unknown[]".to_owned(),
+        },
+        InstanceType::Object => {
+            let validation = match &object.object {
+                Some(validation) => validation,
+                None => return "Record<string, unknown>".to_owned(),
+            };
+            if validation.properties.is_empty() {
+                let value = validation
+                    .additional_properties
+                    .as_deref()
+                    .map_or_else(|| "unknown".to_owned(), type_of);
+                return format!("Record<string, {}>", value);
+            }
+
+            let fields: Vec<String> = validation
+                .properties
+                .iter()
+                .map(|(name, schema)| {
+                    let optional = if validation.required.contains(name) {
+                        ""
+                    } else {
+                        "?"
+                    };
+                    format!("{}{}: {}", name, optional, type_of(schema))
+                })
+                .collect();
+            format!("{{ {} }}", fields.join("; "))
+        }
+    }
+}
+
+fn union(types: impl Iterator<Item = String>) -> String {
+    types.collect::<Vec<String>>().join(" | ")
+}
+
+fn literal(value: &Value) -> String {
+    value.to_string()
+}
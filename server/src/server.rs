@@ -1,9 +1,13 @@
 //! `IuroServer` is an actor. It manages user connections. And available rooms.
 //! Peers communicate through `IuroServer`.
 
-use crate::{prelude::*, room::JoinResult};
+use crate::{
+    prelude::*,
+    room::{JoinResult, SkipResult, UpdateResult},
+};
 use actix::prelude::*;
-use std::collections::HashMap;
+use rand::random;
+use std::{collections::HashMap, time::Duration};
 
 /// Manages connections and rooms, coordinates them
 #[derive(Default)]
@@ -28,6 +32,175 @@ impl IuroServer {
         }
     }
 
+    /// Moves room to `phase` and broadcasts it, scheduling its end if it's timed
+    fn enter_phase(
+        &mut self,
+        room: &str,
+        phase: Phase,
+        duration: Option<Duration>,
+        ctx: &mut Context<Self>,
+    ) -> Result<(), IuroError> {
+        let timer = self
+            .rooms
+            .get_mut(room)
+            .ok_or_else(|| IuroError::NoRoom(room.to_owned()))?
+            .set_phase(phase, duration);
+
+        if let Some(duration) = duration {
+            let room = room.to_owned();
+            ctx.notify_later(PhaseTimeout { room, timer }, duration);
+        }
+
+        let millis = duration.map(|duration| duration.as_millis() as u64);
+        self.send_message(room, &Broadcast::PhaseChanged((phase, millis)))
+    }
+
+    /// Announces next game, it starts once the countdown ends
+    fn start_countdown(&mut self, room: &str, ctx: &mut Context<Self>) -> Result<(), IuroError> {
+        let countdown = self
+            .rooms
+            .get(room)
+            .ok_or_else(|| IuroError::NoRoom(room.to_owned()))?
+            .settings()
+            .countdown();
+
+        self.send_next_up(room)?;
+        self.enter_phase(room, Phase::Countdown, Some(countdown), ctx)
+    }
+
+    /// Broadcasts game that has just started, its time limit is the phase's duration
+    fn game_started(
+        &mut self,
+        room: &str,
+        game: Game,
+        ctx: &mut Context<Self>,
+    ) -> Result<(), IuroError> {
+        // Teams are balanced when the game starts
+        self.send_standings(room)?;
+
+        self.enter_phase(room, Phase::Playing, game.time_limit(), ctx)?;
+        self.send_message(room, &Broadcast::GameStarted(game))?;
+        self.send_next_up(room)
+    }
+
+    /// Broadcasts next games to be played
+    fn send_next_up(&self, room: &str) -> Result<(), IuroError> {
+        let next_up = self
+            .rooms
+            .get(room)
+            .ok_or_else(|| IuroError::NoRoom(room.to_owned()))?
+            .next_up();
+        self.send_message(room, &Broadcast::NextUp(next_up))
+    }
+
+    /// Broadcasts members and wins of each team, if room is in team mode
+    fn send_standings(&self, room: &str) -> Result<(), IuroError> {
+        let standings = self
+            .rooms
+            .get(room)
+            .ok_or_else(|| IuroError::NoRoom(room.to_owned()))?
+            .standings();
+
+        if let Some(standings) = standings {
+            self.send_message(room, &Broadcast::Teams(standings))?;
+        }
+        Ok(())
+    }
+
+    /// Broadcasts every user in the room
+    fn send_roster(&self, room: &str) -> Result<(), IuroError> {
+        let roster = self
+            .rooms
+            .get(room)
+            .ok_or_else(|| IuroError::NoRoom(room.to_owned()))?
+            .roster();
+        self.send_message(room, &Broadcast::Roster(roster))
+    }
+
+    /// Broadcasts the result of a game update, starting next game if it has ended
+    fn game_updated(
+        &mut self,
+        room: &str,
+        update: UpdateResult,
+        ctx: &mut Context<Self>,
+    ) -> Result<(), IuroError> {
+        match update {
+            UpdateResult::Waiting => Ok(()),
+            UpdateResult::Progress(progress) => {
+                self.send_message(room, &Broadcast::GameProgress(progress))
+            }
+            UpdateResult::Moved(board) => self.send_message(room, &Broadcast::BoardUpdated(board)),
+            UpdateResult::NextRound(sequence, players) => {
+                self.send_message(room, &Broadcast::SequenceRevealed((sequence, players)))
+            }
+            UpdateResult::Ended(name, wins, results) => {
+                debug!("Game ended: {:?}", wins);
+                let current = self
+                    .rooms
+                    .get_mut(room)
+                    .ok_or_else(|| IuroError::NoRoom(room.to_owned()))?;
+                let reveal = current.reveal();
+                let settings = current.settings().clone();
+                // Nothing is voted for once the match is over
+                let candidates = if current.match_over() {
+                    current.end_game();
+                    None
+                } else {
+                    Some(current.start_next_vote())
+                };
+
+                if let Some(reveal) = reveal {
+                    self.send_message(room, &Broadcast::GameRevealed(reveal))?;
+                }
+                self.send_message(room, &Broadcast::GameEnded((name, wins, results)))?;
+
+                if let Some(candidates) = candidates {
+                    self.send_message(room, &Broadcast::NextCandidates(candidates))?;
+                    self.enter_phase(room, Phase::Results, Some(settings.results()), ctx)
+                } else {
+                    self.send_standings(room)?;
+                    self.enter_phase(room, Phase::MatchOver, Some(settings.match_over()), ctx)
+                }
+            }
+        }
+    }
+
+    /// Ends vote for the next game, the chosen one starts after the countdown
+    fn next_vote_finished(&mut self, room: &str, ctx: &mut Context<Self>) -> Result<(), IuroError> {
+        self.rooms
+            .get_mut(room)
+            .ok_or_else(|| IuroError::NoRoom(room.to_owned()))?
+            .finish_next_vote();
+        self.start_countdown(room, ctx)
+    }
+
+    /// Ends the room's current timed phase, moving to the next one
+    fn phase_ended(&mut self, room: &str, ctx: &mut Context<Self>) -> Result<(), IuroError> {
+        let current = self
+            .rooms
+            .get_mut(room)
+            .ok_or_else(|| IuroError::NoRoom(room.to_owned()))?;
+
+        match current.phase() {
+            Phase::Countdown => {
+                let game = current.start_game();
+                self.game_started(room, game, ctx)
+            }
+            Phase::Playing => {
+                let update = current.timeout()?;
+                self.game_updated(room, update, ctx)
+            }
+            Phase::Results => self.next_vote_finished(room, ctx),
+            Phase::MatchOver => {
+                current.new_match();
+                self.send_standings(room)?;
+                self.start_countdown(room, ctx)
+            }
+            // Not timed
+            Phase::Lobby | Phase::Paused => Ok(()),
+        }
+    }
+
     /// Removes user from all rooms, returning their address, errors if user isn't in any room
     fn leave_all_rooms(&mut self, id: usize) -> Result<RoomSlot, IuroError> {
         let mut ret = None;
@@ -40,15 +213,21 @@ impl IuroServer {
                 // Must stop current game (if any)
                 room.reset_game();
 
-                // Must delete room if empty
-                if room.sessions().is_empty() {
+                // Must delete room if only bots are left, which stop along with it
+                if !room.has_users() {
                     remove_room = Some(name.clone());
                 }
 
-                let msg = Broadcast::Literal("Someone disconnected");
+                let msgs = [
+                    Broadcast::Literal("Someone disconnected"),
+                    Broadcast::PhaseChanged((room.phase(), None)),
+                    Broadcast::Roster(room.roster()),
+                ];
                 for slot in room.sessions().values() {
-                    // Ignores recipients with a full mailbox
-                    let _ = slot.recipient.do_send(msg.clone());
+                    for msg in &msgs {
+                        // Ignores recipients with a full mailbox
+                        let _ = slot.recipient.do_send(msg.clone());
+                    }
                 }
 
                 ret = Some(slot);
@@ -73,11 +252,14 @@ impl Handler<Connect> for IuroServer {
     type Result = ();
 
     fn handle(&mut self, msg: Connect, _: &mut Context<Self>) -> Self::Result {
-        trace!("Websocket connection stablished: id = {}", msg.id);
+        trace!("Client connection stablished: id = {}", msg.id);
         let slot = RoomSlot {
             recipient: msg.addr,
             name: format!("user-{}", msg.id % 0xFFF),
             wins: 0,
+            team: None,
+            rtt: None,
+            bot: false,
         };
         self.unbound_sessions.insert(msg.id, slot);
     }
@@ -87,7 +269,7 @@ impl Handler<Disconnect> for IuroServer {
     type Result = Result<(), IuroError>;
 
     fn handle(&mut self, msg: Disconnect, _: &mut Context<Self>) -> Self::Result {
-        trace!("Websocket connection closed: id = {}", msg.id);
+        trace!("Client connection closed: id = {}", msg.id);
         if self.unbound_sessions.remove(&msg.id).is_none() {
             let _ = self.leave_all_rooms(msg.id)?;
         }
@@ -115,26 +297,188 @@ impl Handler<ChatMessage> for IuroServer {
     }
 }
 
-impl Handler<UserGameInput> for IuroServer {
+impl Handler<TeamChatMessage> for IuroServer {
     type Result = Result<(), IuroError>;
 
-    fn handle(&mut self, input: UserGameInput, _: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, msg: TeamChatMessage, _: &mut Context<Self>) -> Self::Result {
         let room = self
             .rooms
-            .get_mut(&input.room)
-            .ok_or_else(|| IuroError::NoRoom(input.room.clone()))?;
-        let (name, wins) = room.update(input.id, input.input)?;
+            .get(&msg.room)
+            // This should never happen
+            .ok_or_else(|| IuroError::NoRoom(msg.room.clone()))?;
+        let name = &room
+            .sessions()
+            .get(&msg.id)
+            // This should never happen
+            .ok_or(IuroError::AddrNotFound(msg.id))?
+            .name;
+
+        let broadcast = Broadcast::Text(format!("{} (team): {}", name, msg.msg));
+        debug!("Broadcasting to team: {:?}", broadcast);
+        for slot in room.teammates(msg.id)? {
+            // Ignores recipients with a full mailbox
+            let _ = slot.recipient.do_send(broadcast.clone());
+        }
+        Ok(())
+    }
+}
+
+impl Handler<SetTeamMode> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, msg: SetTeamMode, _: &mut Context<Self>) -> Self::Result {
+        let room = self
+            .rooms
+            .get_mut(&msg.room)
+            // This should never happen
+            .ok_or_else(|| IuroError::NoRoom(msg.room.clone()))?;
+        room.set_team_mode(msg.id, msg.enabled)?;
+
+        // Users must know teams were disbanded
+        let standings = room.standings().unwrap_or_default();
+        self.send_message(&msg.room, &Broadcast::Teams(standings))?;
+        self.send_roster(&msg.room)
+    }
+}
+
+impl Handler<ChooseTeam> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, msg: ChooseTeam, _: &mut Context<Self>) -> Self::Result {
+        self.rooms
+            .get_mut(&msg.room)
+            // This should never happen
+            .ok_or_else(|| IuroError::NoRoom(msg.room.clone()))?
+            .choose_team(msg.id, msg.team)?;
+        self.send_standings(&msg.room)?;
+        self.send_roster(&msg.room)
+    }
+}
+
+impl Handler<SetPlaylist> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, msg: SetPlaylist, _: &mut Context<Self>) -> Self::Result {
+        self.rooms
+            .get_mut(&msg.room)
+            // This should never happen
+            .ok_or_else(|| IuroError::NoRoom(msg.room.clone()))?
+            .set_playlist(msg.id, msg.playlist)?;
+        self.send_next_up(&msg.room)?;
+
+        // Votes for the next game were discarded along with the old candidates
+        if let Some(candidates) = self.rooms.get(&msg.room).and_then(Room::candidates) {
+            self.send_message(&msg.room, &Broadcast::NextCandidates(candidates))?;
+        }
+        Ok(())
+    }
+}
+
+impl Handler<VoteSkip> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, msg: VoteSkip, ctx: &mut Context<Self>) -> Self::Result {
+        let result = self
+            .rooms
+            .get_mut(&msg.room)
+            // This should never happen
+            .ok_or_else(|| IuroError::NoRoom(msg.room.clone()))?
+            .vote_skip(msg.id)?;
+
+        match result {
+            SkipResult::Votes(votes, needed) => {
+                self.send_message(&msg.room, &Broadcast::SkipVotes((votes, needed)))
+            }
+            SkipResult::Skipped(update) => {
+                self.send_message(&msg.room, &Broadcast::Literal("Game skipped by vote"))?;
+                self.game_updated(&msg.room, update, ctx)
+            }
+        }
+    }
+}
+
+impl Handler<VoteNext> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, msg: VoteNext, ctx: &mut Context<Self>) -> Self::Result {
+        let room = self
+            .rooms
+            .get_mut(&msg.room)
+            // This should never happen
+            .ok_or_else(|| IuroError::NoRoom(msg.room.clone()))?;
+        let (tally, everybody) = room.vote_next(msg.id, msg.candidate)?;
 
-        if !wins.is_empty() {
-            debug!("Game ended: {:?}", wins);
-            let game = room.start_game();
-            self.send_message(&input.room, &Broadcast::GameEnded((name, wins)))?;
-            self.send_message(&input.room, &Broadcast::GameStarted(game))?;
+        self.send_message(&msg.room, &Broadcast::NextVotes(tally))?;
+        // Results phase ends early
+        if everybody {
+            self.next_vote_finished(&msg.room, ctx)?;
         }
         Ok(())
     }
 }
 
+impl Handler<UserGameInput> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, input: UserGameInput, ctx: &mut Context<Self>) -> Self::Result {
+        let update = self
+            .rooms
+            .get_mut(&input.room)
+            .ok_or_else(|| IuroError::NoRoom(input.room.clone()))?
+            .update(input.id, input.input)?;
+        self.game_updated(&input.room, update, ctx)
+    }
+}
+
+impl Handler<PhaseTimeout> for IuroServer {
+    type Result = ();
+
+    fn handle(&mut self, timeout: PhaseTimeout, ctx: &mut Context<Self>) -> Self::Result {
+        // Room may have been deleted, or its phase changed, before the timer fired
+        match self.rooms.get(&timeout.room) {
+            Some(room) if room.timer() == timeout.timer => {}
+            _ => return,
+        }
+
+        if let Err(err) = self.phase_ended(&timeout.room, ctx) {
+            error!("Failed to end phase: {}", err);
+        }
+    }
+}
+
+impl Handler<SetPaused> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, msg: SetPaused, ctx: &mut Context<Self>) -> Self::Result {
+        let room = self
+            .rooms
+            .get_mut(&msg.room)
+            // This should never happen
+            .ok_or_else(|| IuroError::NoRoom(msg.room.clone()))?;
+
+        if msg.paused {
+            room.pause(msg.id)?;
+            self.enter_phase(&msg.room, Phase::Paused, None, ctx)
+        } else {
+            // Timer continues from where it stopped
+            let (phase, remaining) = room.resume(msg.id)?;
+            self.enter_phase(&msg.room, phase, remaining, ctx)
+        }
+    }
+}
+
+impl Handler<SetMatchSettings> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, msg: SetMatchSettings, _: &mut Context<Self>) -> Self::Result {
+        self.rooms
+            .get_mut(&msg.room)
+            // This should never happen
+            .ok_or_else(|| IuroError::NoRoom(msg.room.clone()))?
+            .set_settings(msg.id, msg.settings)
+    }
+}
+
 impl Handler<ListRooms> for IuroServer {
     type Result = MessageResult<ListRooms>;
 
@@ -157,6 +501,7 @@ impl Handler<SetUsername> for IuroServer {
                 // This should never happen
                 .ok_or(IuroError::AddrNotFound(set.user_id))?
                 .name = set.name;
+            self.send_roster(&room)?;
         } else if let Some(slot) = self.unbound_sessions.get_mut(&set.user_id) {
             slot.name = set.name;
         } else {
@@ -166,10 +511,96 @@ impl Handler<SetUsername> for IuroServer {
     }
 }
 
+impl Handler<AddBot> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, msg: AddBot, ctx: &mut Context<Self>) -> Self::Result {
+        msg.settings.validate()?;
+        let room = self
+            .rooms
+            .get_mut(&msg.room)
+            // This should never happen
+            .ok_or_else(|| IuroError::NoRoom(msg.room.clone()))?;
+
+        let id = random();
+        let name = format!("bot-{}", id % 0xFFF);
+        let addr = ctx.address();
+        // Bot only starts playing if the host may add it
+        let bot = || RoomSlot {
+            recipient: Bot::new(id, name.clone(), msg.room.clone(), &msg.settings, addr)
+                .start()
+                .recipient(),
+            name,
+            wins: 0,
+            team: None,
+            rtt: None,
+            bot: true,
+        };
+
+        match room.add_bot(msg.id, id, bot)? {
+            JoinResult::Ready => {
+                self.send_roster(&msg.room)?;
+                self.start_countdown(&msg.room, ctx)
+            }
+            JoinResult::NoGame => {
+                self.send_roster(&msg.room)?;
+                self.send_standings(&msg.room)
+            }
+            JoinResult::Full => Err(IuroError::FullRoom(msg.room)),
+        }
+    }
+}
+
+impl Handler<RemoveBot> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, msg: RemoveBot, _: &mut Context<Self>) -> Self::Result {
+        let room = self
+            .rooms
+            .get_mut(&msg.room)
+            // This should never happen
+            .ok_or_else(|| IuroError::NoRoom(msg.room.clone()))?;
+        room.remove_bot(msg.id, &msg.name)?;
+
+        // Room is no longer full
+        room.reset_game();
+        let phase = room.phase();
+        self.send_message(&msg.room, &Broadcast::PhaseChanged((phase, None)))?;
+        self.send_roster(&msg.room)?;
+        self.send_standings(&msg.room)
+    }
+}
+
+impl Handler<UpdateRtt> for IuroServer {
+    type Result = Result<(), IuroError>;
+
+    fn handle(&mut self, msg: UpdateRtt, _: &mut Context<Self>) -> Self::Result {
+        let rtt = msg.rtt.as_millis() as u64;
+        if let Some(slot) = self.unbound_sessions.get_mut(&msg.id) {
+            slot.set_rtt(rtt);
+            return Ok(());
+        }
+
+        let (room, changed) = self
+            .rooms
+            .iter_mut()
+            .find_map(|(name, room)| {
+                let changed = room.sessions_mut().get_mut(&msg.id)?.set_rtt(rtt);
+                Some((name.clone(), changed))
+            })
+            .ok_or(IuroError::AddrNotFound(msg.id))?;
+        // Every pong would otherwise send the roster to the whole room
+        if changed {
+            self.send_roster(&room)?;
+        }
+        Ok(())
+    }
+}
+
 impl Handler<Join> for IuroServer {
     type Result = Result<(), IuroError>;
 
-    fn handle(&mut self, msg: Join, _: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, msg: Join, ctx: &mut Context<Self>) -> Self::Result {
         let Join { id, name } = msg;
 
         // Remove room slot
@@ -180,15 +611,18 @@ impl Handler<Join> for IuroServer {
         };
 
         // Creates room on demand
-        let join = self
-            .rooms
-            .entry(name.clone())
-            .or_insert_with(Room::default)
-            .join(id, slot);
+        let join = self.rooms.entry(name.clone()).or_default().join(id, slot);
 
         match join {
-            JoinResult::NewGame(game) => self.send_message(&name, &Broadcast::GameStarted(game))?,
-            JoinResult::NoGame => {}
+            JoinResult::Ready => {
+                self.send_roster(&name)?;
+                self.start_countdown(&name, ctx)?;
+            }
+            JoinResult::NoGame => {
+                self.send_roster(&name)?;
+                self.send_standings(&name)?;
+                self.send_next_up(&name)?;
+            }
             JoinResult::Full => return Err(IuroError::FullRoom(name.clone())),
         }
 
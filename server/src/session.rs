@@ -1,69 +1,102 @@
+use crate::protocol;
 use actix::prelude::*;
-use actix_web_actors::ws;
+use futures::sync::mpsc::UnboundedSender;
 use log::{debug, error, trace};
-use serde_json::to_string;
+use rand::random;
 use std::{borrow::Cow, fmt, fmt::Debug, fmt::Formatter, time::Duration, time::Instant};
 
-use crate::{handle_text, prelude::Response, prelude::*};
+use crate::{
+    handle_binary, handle_text,
+    prelude::Response,
+    prelude::*,
+    protocol::{Encoding, Frame},
+};
 
-/// How often heartbeat pings are checked for
-const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// How often heartbeat pings are checked for, and sent by transports that can
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 /// How long before lack of client response causes a timeout
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often batched taps are sent to `IuroServer`
+const TAPS_INTERVAL: Duration = Duration::from_millis(250);
 
-/// Each websocket connection generates a session that exists until it's closed
+/// Each client connection generates a session that exists until it's closed
+///
+/// Sessions don't know which transport their client uses, transports forward client's
+/// messages as `ClientMessage` and send the frames written to `outbox`
 pub struct IuroSession {
     /// Unique session id
     pub id: usize,
-    /// Client must send ping at least once per 10 seconds (CLIENT_TIMEOUT),
-    /// otherwise we drop the connection.
+    /// Client must show it's alive at least once per 30 seconds (CLIENT_TIMEOUT),
+    /// otherwise we drop the connection. Every message received counts.
     pub heartbeat: Instant,
     /// Room user is authenticated to
     pub room: Option<String>,
     /// Iuro server's address
     pub addr: Addr<IuroServer>,
+    /// Taps received since last batch was sent
+    pub taps: u32,
+    /// Protocol version negotiated with the client, see `protocol`
+    pub protocol: u32,
+    /// How messages are serialized, chosen when connecting
+    pub encoding: Encoding,
+    /// Frames to be sent to the client by its transport
+    pub outbox: UnboundedSender<Frame>,
 }
 
-/// WebSocket message handler
-impl StreamHandler<ws::Message, ws::ProtocolError> for IuroSession {
-    fn handle(&mut self, msg: ws::Message, ctx: &mut Self::Context) {
+/// Message received from client, by any transport
+#[derive(Message, Debug)]
+pub enum ClientMessage {
+    Text(String),
+    Binary(Vec<u8>),
+    Heartbeat,
+    /// Transport measured the round-trip time to the client
+    Rtt(Duration),
+    /// Client closed the connection
+    Close,
+}
+
+impl Handler<ClientMessage> for IuroSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: ClientMessage, ctx: &mut Self::Context) -> Self::Result {
+        // Any traffic means the client is alive
+        self.heartbeat = Instant::now();
+
         match msg {
-            ws::Message::Text(text) => {
-                debug!("Websocket Broadcast: {:?}", text);
+            ClientMessage::Text(text) => {
+                debug!("Client Broadcast: {:?}", text);
                 if let Err(err) = handle_text(&text, self, ctx) {
-                    if let Ok(json) = to_string(&Response::Error(err.to_string())) {
-                        ctx.text(json)
-                    } else {
-                        error!("Failed to serialize `Response`");
-                        debug_assert!(false, "Failed to serialize `Response`");
-                    }
+                    self.respond(&Response::Error(err.to_string()), ctx);
                 }
             }
-            // Web-Browsers don't support built-in Ping/Pong, we must mock it with binary data
-            ws::Message::Binary(raw) => {
-                if raw == [0x09][..] {
-                    trace!("Hearbeat");
-                    self.heartbeat = Instant::now();
+            ClientMessage::Binary(raw) => {
+                if self.encoding == Encoding::MessagePack {
+                    if let Err(err) = handle_binary(&raw, self, ctx) {
+                        self.respond(&Response::Error(err.to_string()), ctx);
+                    }
                 } else {
                     error!("Unexpected binary data: {:?}", raw);
                 }
             }
-            ws::Message::Close(_) => ctx.stop(),
-            ws::Message::Ping(_) => (),
-            ws::Message::Pong(_) => (),
-            ws::Message::Nop => (),
+            ClientMessage::Heartbeat => trace!("Hearbeat"),
+            ClientMessage::Rtt(rtt) => {
+                trace!("Round-trip time: {:?}", rtt);
+                self.addr.do_send(UpdateRtt { id: self.id, rtt });
+            }
+            ClientMessage::Close => ctx.stop(),
         }
     }
 }
 
 impl Actor for IuroSession {
-    type Context = ws::WebsocketContext<Self>;
+    type Context = Context<Self>;
 
     /// Method is called on actor start.
-    /// We register ws session with IuroServer
+    /// We register session with IuroServer
     fn started(&mut self, ctx: &mut Self::Context) {
         // We'll start heartbeat process on session start.
         self.heartbeat(ctx);
+        self.flush_taps(ctx);
 
         let (id, addr) = (self.id, ctx.address().recipient());
         self.addr.do_send(Connect { id, addr });
@@ -76,16 +109,56 @@ impl Actor for IuroSession {
 }
 
 impl IuroSession {
+    pub fn new(addr: Addr<IuroServer>, encoding: Encoding, outbox: UnboundedSender<Frame>) -> Self {
+        Self {
+            // This is not ideal since `ThreadRng` is not cached,
+            // but it's better than needing an `Actor` to generate an id
+            id: random(),
+            heartbeat: Instant::now(),
+            room: None,
+            taps: 0,
+            // Clients that never say hello speak the first version
+            protocol: 0,
+            encoding,
+            addr,
+            outbox,
+        }
+    }
+
+    /// Sends response to the client, in the protocol version and encoding it speaks
+    ///
+    /// Session stops if its transport is gone
+    pub fn respond(&self, response: &Response, ctx: &mut Context<Self>) {
+        let frame = match protocol::encode(response, self.protocol, self.encoding) {
+            Ok(frame) => frame,
+            Err(err) => {
+                // This should never happen
+                error!("Failed to serialize `Response`: {}", err);
+                debug_assert!(false, "Failed to serialize `Response`");
+                return;
+            }
+        };
+
+        match &frame {
+            Frame::Text(text) => trace!("Sending: {}", text),
+            Frame::Binary(_) => trace!("Sending: {:?}", response),
+        }
+        if self.outbox.unbounded_send(frame).is_err() {
+            trace!("Client transport closed, disconnecting!");
+            ctx.stop();
+        }
+    }
+
     /// helper method that sends ping to client every second.
     ///
     /// also this method checks heartbeats from client
-    fn heartbeat(&self, ctx: &mut ws::WebsocketContext<Self>) {
+    fn heartbeat(&self, ctx: &mut Context<Self>) {
         let id = self.id;
         ctx.run_interval(HEARTBEAT_INTERVAL, move |act, ctx| {
             // Check client heartbeats
             if Instant::now().duration_since(act.heartbeat) > CLIENT_TIMEOUT {
                 // Heartbeat timed out
-                trace!("Websocket Client heartbeat failed, disconnecting!");
+                trace!("Client heartbeat failed, disconnecting!");
 
                 // Notify iuro server
                 act.addr.do_send(Disconnect { id });
@@ -95,9 +168,29 @@ impl IuroSession {
             }
         });
     }
+
+    /// Sends taps batched since last interval to `IuroServer`
+    ///
+    /// Errors are ignored, taps sent after the game ended are simply discarded
+    fn flush_taps(&self, ctx: &mut Context<Self>) {
+        ctx.run_interval(TAPS_INTERVAL, |act, _| {
+            if act.taps == 0 {
+                return;
+            }
+
+            if let Some(room) = act.room.clone() {
+                act.addr.do_send(UserGameInput {
+                    id: act.id,
+                    room,
+                    input: GameInput::ButtonMash(act.taps),
+                });
+            }
+            act.taps = 0;
+        });
+    }
 }
 
-/// Handle messages from iuro server, we simply send it to peer websocket
+/// Handle messages from iuro server, we simply send it to the client
 impl Handler<Broadcast> for IuroSession {
     type Result = ();
 
@@ -106,12 +199,23 @@ impl Handler<Broadcast> for IuroSession {
             Broadcast::Text(msg) => Response::Text(Cow::Owned(msg)),
             Broadcast::Literal(msg) => Response::Text(Cow::Borrowed(msg)),
             Broadcast::GameStarted(game) => Response::GameStarted(game),
-            Broadcast::GameEnded(wins) => Response::GameEnded(wins),
+            Broadcast::GameProgress(progress) => Response::GameProgress(progress),
+            Broadcast::SequenceRevealed(round) => Response::SequenceRevealed(round),
+            Broadcast::BoardUpdated(board) => Response::BoardUpdated(board),
+            Broadcast::NextUp(games) => Response::NextUp(games),
+            Broadcast::Teams(teams) => Response::Teams(teams),
+            Broadcast::Roster(roster) => Response::Roster(roster),
+            Broadcast::PhaseChanged(phase) => Response::PhaseChanged(phase),
+            Broadcast::SkipVotes(votes) => Response::SkipVotes(votes),
+            Broadcast::NextCandidates(games) => Response::NextCandidates(games),
+            Broadcast::NextVotes(votes) => Response::NextVotes(votes),
+            Broadcast::GameRevealed(reveal) => Response::GameRevealed(reveal),
+            Broadcast::GameEnded((name, wins, results)) => {
+                Response::GameEnded((Cow::Borrowed(name), wins, results))
+            }
         };
 
-        if let Ok(txt) = to_string(&resp) {
-            ctx.text(txt);
-        }
+        self.respond(&resp, ctx);
     }
 }
 
@@ -121,7 +225,11 @@ impl Debug for IuroSession {
             .field("id", &self.id)
             .field("heartbeat", &self.heartbeat)
             .field("room", &self.room)
+            .field("taps", &self.taps)
+            .field("protocol", &self.protocol)
+            .field("encoding", &self.encoding)
             .field("addr", &"Addr<IuroServer>")
+            .field("outbox", &"UnboundedSender<Frame>")
             .finish()
     }
 }
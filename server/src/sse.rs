@@ -0,0 +1,83 @@
+//! Server-Sent Events transport, for clients behind proxies that break websockets
+//!
+//! `GET /sse/` streams responses, its first event is named `session` and carries the session id.
+//! Commands are sent with `POST /sse/{id}` and heartbeats with `POST /sse/{id}/heartbeat`.
+
+use crate::prelude::*;
+use crate::protocol::{Encoding, Frame};
+use crate::session::ClientMessage;
+use actix::prelude::*;
+use actix_web::{error::ErrorInternalServerError, http::header, web, HttpResponse};
+use bytes::Bytes;
+use futures::{stream, sync::mpsc::unbounded};
+use std::{collections::HashMap, sync::Mutex};
+
+/// Sessions of SSE clients, so posted commands can find them
+#[derive(Default)]
+pub struct SseSessions(Mutex<HashMap<usize, Addr<IuroSession>>>);
+
+impl SseSessions {
+    fn insert(&self, id: usize, session: Addr<IuroSession>) {
+        let mut sessions = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        // Forgets sessions that already stopped
+        sessions.retain(|_, session| session.connected());
+        sessions.insert(id, session);
+    }
+
+    fn send(&self, id: usize, msg: ClientMessage) -> HttpResponse {
+        let mut sessions = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        match sessions.get(&id) {
+            Some(session) if session.connected() => {
+                session.do_send(msg);
+                HttpResponse::Accepted().finish()
+            }
+            _ => {
+                sessions.remove(&id);
+                HttpResponse::NotFound().finish()
+            }
+        }
+    }
+}
+
+/// Starts a session, streaming its responses as events
+pub fn sse_route(
+    srv: web::Data<Addr<IuroServer>>,
+    sessions: web::Data<SseSessions>,
+) -> HttpResponse {
+    let (sender, receiver) = unbounded();
+    // Events are text, so SSE clients always use JSON
+    let session = IuroSession::new(srv.get_ref().clone(), Encoding::Json, sender);
+    let id = session.id;
+    sessions.insert(id, session.start());
+
+    let hello = stream::once(Ok(Bytes::from(format!("event: session\ndata: {}\n\n", id))));
+    let events = receiver.filter_map(|frame| match frame {
+        // Serialized JSON never has line breaks, so every response fits in one data line
+        Frame::Text(text) => Some(Bytes::from(format!("data: {}\n\n", text))),
+        Frame::Binary(_) => None,
+    });
+    let body = hello
+        .chain(events)
+        .map_err(|()| ErrorInternalServerError("Session closed"));
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        // Stops nginx from buffering events
+        .header("X-Accel-Buffering", "no")
+        .streaming(body)
+}
+
+/// Sends command in body to the session, its response arrives as an event
+pub fn sse_command(
+    id: web::Path<usize>,
+    body: String,
+    sessions: web::Data<SseSessions>,
+) -> HttpResponse {
+    sessions.send(*id, ClientMessage::Text(body))
+}
+
+/// Keeps the session alive, clients must call it as often as websocket clients ping
+pub fn sse_heartbeat(id: web::Path<usize>, sessions: web::Data<SseSessions>) -> HttpResponse {
+    sessions.send(*id, ClientMessage::Heartbeat)
+}
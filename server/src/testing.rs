@@ -0,0 +1,35 @@
+//! Helpers shared by unit tests, users need a running `System` since each has a recipient
+
+use crate::prelude::*;
+use actix::prelude::*;
+use std::collections::HashMap;
+
+/// Discards every broadcast, rooms need a recipient for each user
+pub struct Sink;
+
+impl Actor for Sink {
+    type Context = Context<Self>;
+}
+
+impl Handler<Broadcast> for Sink {
+    type Result = ();
+
+    fn handle(&mut self, _: Broadcast, _: &mut Context<Self>) {}
+}
+
+/// Slot of user `id`, named `user-{id}`
+pub fn slot(id: usize, bot: bool) -> RoomSlot {
+    RoomSlot {
+        recipient: Sink.start().recipient(),
+        name: format!("user-{}", id),
+        wins: 0,
+        team: None,
+        rtt: None,
+        bot,
+    }
+}
+
+/// Users `0..users`, none of them a bot
+pub fn sessions(users: usize) -> HashMap<usize, RoomSlot> {
+    (0..users).map(|id| (id, slot(id, false))).collect()
+}
@@ -0,0 +1,133 @@
+//! Websocket transport, the one browsers use
+
+use crate::prelude::*;
+use crate::protocol::{self, Encoding, Frame};
+use crate::session::{ClientMessage, HEARTBEAT_INTERVAL};
+use actix::prelude::*;
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use futures::sync::mpsc::unbounded;
+use std::time::{Duration, Instant};
+
+/// Relays frames between a websocket and its `IuroSession`
+pub struct WsConnection {
+    /// Session is started along with the connection, once the handshake succeeded
+    session: Option<Addr<IuroSession>>,
+    addr: Addr<IuroServer>,
+    encoding: Encoding,
+    /// Pings carry the milliseconds elapsed since then, so pongs tell the round-trip time
+    started: Instant,
+}
+
+impl Actor for WsConnection {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let (sender, receiver) = unbounded();
+        let session = IuroSession::new(self.addr.clone(), self.encoding, sender);
+        self.session = Some(session.start());
+        ctx.add_stream(receiver);
+
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
+            ctx.ping(&act.started.elapsed().as_millis().to_string());
+        });
+    }
+
+    fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        self.send(ClientMessage::Close);
+        Running::Stop
+    }
+}
+
+impl WsConnection {
+    fn send(&self, msg: ClientMessage) {
+        if let Some(session) = &self.session {
+            session.do_send(msg);
+        }
+    }
+}
+
+/// WebSocket message handler
+impl StreamHandler<ws::Message, ws::ProtocolError> for WsConnection {
+    fn handle(&mut self, msg: ws::Message, ctx: &mut Self::Context) {
+        match msg {
+            ws::Message::Text(text) => self.send(ClientMessage::Text(text)),
+            // Web-Browsers can't send built-in pings, so they mock it with binary data
+            ws::Message::Binary(raw) => {
+                if raw == [0x09][..] {
+                    self.send(ClientMessage::Heartbeat);
+                } else {
+                    self.send(ClientMessage::Binary(raw.to_vec()));
+                }
+            }
+            ws::Message::Close(_) => ctx.stop(),
+            ws::Message::Ping(msg) => {
+                ctx.pong(&msg);
+                self.send(ClientMessage::Heartbeat);
+            }
+            // Browsers answer pings by themselves, echoing when they were sent
+            ws::Message::Pong(msg) => match msg.parse::<u64>() {
+                Ok(sent) => {
+                    let elapsed = self.started.elapsed().as_millis() as u64;
+                    let rtt = Duration::from_millis(elapsed.saturating_sub(sent));
+                    self.send(ClientMessage::Rtt(rtt));
+                }
+                Err(_) => self.send(ClientMessage::Heartbeat),
+            },
+            ws::Message::Nop => (),
+        }
+    }
+}
+
+/// Sends session's frames to the websocket, closing it once session stops
+impl StreamHandler<Frame, ()> for WsConnection {
+    fn handle(&mut self, frame: Frame, ctx: &mut Self::Context) {
+        match frame {
+            Frame::Text(text) => ctx.text(text),
+            Frame::Binary(raw) => ctx.binary(raw),
+        }
+    }
+
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+/// Entry point for our route
+pub fn iuro_route(
+    req: HttpRequest,
+    stream: web::Payload,
+    srv: web::Data<Addr<IuroServer>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let connection = WsConnection {
+        session: None,
+        addr: srv.get_ref().clone(),
+        encoding: encoding(&req),
+        started: Instant::now(),
+    };
+    // Upgrades connection to websocket, agreeing to the subprotocol if client asked for it
+    ws::start_with_protocols(connection, &[protocol::MSGPACK_PROTOCOL], &req, stream)
+}
+
+/// MessagePack is used if client asks for its subprotocol or `encoding=msgpack` query param
+fn encoding(req: &HttpRequest) -> Encoding {
+    let subprotocol = req
+        .headers()
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|protocols| protocols.to_str().ok())
+        .is_some_and(|protocols| {
+            protocols
+                .split(',')
+                .any(|p| p.trim() == protocol::MSGPACK_PROTOCOL)
+        });
+    let query = req
+        .query_string()
+        .split('&')
+        .any(|param| param == "encoding=msgpack");
+
+    if subprotocol || query {
+        Encoding::MessagePack
+    } else {
+        Encoding::Json
+    }
+}
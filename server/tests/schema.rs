@@ -0,0 +1,17 @@
+//! Protocol types changed without regenerating the checked-in schemas if this fails
+
+use iuro_server::schema;
+use std::{fs, path::Path};
+
+#[test]
+fn checked_in_schemas_are_up_to_date() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schema");
+    for (file, contents) in schema::files() {
+        let checked_in = fs::read_to_string(dir.join(file)).unwrap_or_default();
+        assert!(
+            checked_in == contents,
+            "schema/{} is stale, regenerate it with `cargo run -- schema`",
+            file
+        );
+    }
+}